@@ -0,0 +1,256 @@
+use tables::S;
+
+/// An AES state or round key, byte i being row i % 4 of column i / 4
+pub type Block = [u8;16];
+
+/// Round constants of the key schedule
+static RCON: [u8;10] = [0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0x1b, 0x36];
+
+/// Multiplication by x in the AES field
+pub fn xtime(x: u8) -> u8 {
+    (x << 1) ^ if x & 0x80 != 0 { 0x1b } else { 0 }
+}
+
+/// Multiplication in the AES field, e.g. by the coefficients of MixColumns or InvMixColumns
+pub fn mul(x: u8, y: u8) -> u8 {
+    let (mut x, mut product) = (x, 0);
+
+    for b in 0..8 {
+        if (y >> b) & 0x1 == 1 {
+            product ^= x;
+        }
+
+        x = xtime(x);
+    }
+
+    product
+}
+
+/// Entry `x` of the encryption T-table Te0..Te3 with index `table`. Te0[x] is the column
+/// (2 S[x], S[x], S[x], 3 S[x]) as a big-endian word, and each next table rotates it right by a
+/// byte, so Te_r holds the MixColumns contribution of a byte in row r
+pub fn t_table(table: usize, x: u8) -> u32 {
+    let s = S[x as usize];
+    let column = (xtime(s) as u32) << 24 | (s as u32) << 16 | (s as u32) << 8
+                 | (xtime(s) ^ s) as u32;
+
+    column.rotate_right(8*table as u32)
+}
+
+/// The output byte of the first MixColumns in which the S-box output of state byte `position` is
+/// multiplied by 2, together with the other state bytes that enter it and their coefficients.
+/// ShiftRows moves byte (r, c) to column c - r, and row r of a column is 2 a_r ^ 3 a_r+1 ^ a_r+2 ^
+/// a_r+3
+pub fn mix_columns_target(position: usize) -> (usize, [(usize, u8);3]) {
+    let (row, column) = (position % 4, (position / 4 + 4 - position % 4) % 4);
+    let mut others = [(0, 0);3];
+
+    for (i, other) in others.iter_mut().enumerate() {
+        let r = (row + i + 1) % 4;
+        let coefficient = if i == 0 { 3 } else { 1 };
+        *other = (r + 4*((column + r) % 4), coefficient);
+    }
+
+    (row + 4*column, others)
+}
+
+/// Converts the 16 bytes of a cipher block into a state
+pub fn block(bytes: &[u8]) -> Block {
+    if bytes.len() != 16 {
+        panic!("[ERROR] block: AES blocks are 16 bytes.");
+    }
+
+    let mut state = [0u8;16];
+    state.copy_from_slice(bytes);
+
+    state
+}
+
+/// Combines the key bytes found by `attack_all` into a round key
+pub fn round_key(key_bytes: &Vec<usize>) -> Block {
+    let mut round_key = [0u8;16];
+
+    for (byte, &k) in round_key.iter_mut().zip(key_bytes) {
+        *byte = k as u8;
+    }
+
+    round_key
+}
+
+/// Expands a key of 16, 24 or 32 bytes into its round keys
+pub fn key_expansion(key: &[u8]) -> Vec<Block> {
+    let nk = key.len() / 4;
+    let num_rounds = match key.len() {
+        16 => 10,
+        24 => 12,
+        32 => 14,
+        _  => panic!("[ERROR] key_expansion: invalid key size."),
+    };
+
+    let mut words: Vec<[u8;4]> = key.chunks(4).map(|w| [w[0], w[1], w[2], w[3]]).collect();
+
+    for i in nk..4*(num_rounds + 1) {
+        let mut w = words[i-1];
+
+        if i % nk == 0 {
+            w = [S[w[1] as usize] ^ RCON[i/nk - 1], S[w[2] as usize], S[w[3] as usize],
+                 S[w[0] as usize]];
+        } else if nk > 6 && i % nk == 4 {
+            w = [S[w[0] as usize], S[w[1] as usize], S[w[2] as usize], S[w[3] as usize]];
+        }
+
+        let previous = words[i-nk];
+        words.push([w[0] ^ previous[0], w[1] ^ previous[1], w[2] ^ previous[2],
+                    w[3] ^ previous[3]]);
+    }
+
+    words.chunks(4).map(|r| {
+        let mut round_key = [0u8;16];
+
+        for (i, w) in r.iter().enumerate() {
+            round_key[4*i..4*i+4].copy_from_slice(w);
+        }
+
+        round_key
+    }).collect()
+}
+
+/// The AES keys that give the round keys recovered so far, each with whether its key schedule
+/// gives all of them. The first round key is the AES-128 key, and the first two round keys hold
+/// the AES-192 and AES-256 keys. AES-128 and AES-192 keys do not match the key schedule when the
+/// second round key belongs to a longer key
+pub fn key_candidates(round_keys: &[Block]) -> Vec<(&'static str, Vec<u8>, bool)> {
+    let mut candidates = vec![("AES-128", round_keys[0].to_vec())];
+
+    if round_keys.len() > 1 {
+        candidates.push(("AES-192", [&round_keys[0][..], &round_keys[1][..8]].concat()));
+        candidates.push(("AES-256", [&round_keys[0][..], &round_keys[1][..]].concat()));
+    }
+
+    candidates.into_iter().map(|(name, key)| {
+        let consistent = round_keys.iter().zip(&key_expansion(&key)).all(|(r, e)| r == e);
+        (name, key, consistent)
+    }).collect()
+}
+
+/// Reconstructs an AES-128 key from its last round key, by running the key schedule backwards
+pub fn invert_key_schedule(last_round_key: &Block) -> Block {
+    let mut words: Vec<[u8;4]> = vec![[0;4];40];
+    words.extend(last_round_key.chunks(4).map(|w| [w[0], w[1], w[2], w[3]]));
+
+    for i in (4..44).rev() {
+        let w = words[i-1];
+        let w = match i % 4 {
+            0 => [S[w[1] as usize] ^ RCON[i/4 - 1], S[w[2] as usize], S[w[3] as usize],
+                  S[w[0] as usize]],
+            _ => w,
+        };
+
+        for j in 0..4 {
+            words[i-4][j] = words[i][j] ^ w[j];
+        }
+    }
+
+    let mut key = [0u8;16];
+
+    for (i, w) in words[..4].iter().enumerate() {
+        key[4*i..4*i+4].copy_from_slice(w);
+    }
+
+    key
+}
+
+/// Applies SubBytes to a state
+pub fn sub_bytes(state: &Block) -> Block {
+    let mut result = [0u8;16];
+
+    for (r, &x) in result.iter_mut().zip(state.iter()) {
+        *r = S[x as usize];
+    }
+
+    result
+}
+
+/// Applies ShiftRows to a state, row r is rotated left by r columns
+pub fn shift_rows(state: &Block) -> Block {
+    let mut result = [0u8;16];
+
+    for i in 0..16 {
+        result[i] = state[(i + 4*(i % 4)) % 16];
+    }
+
+    result
+}
+
+/// Applies MixColumns to a state
+pub fn mix_columns(state: &Block) -> Block {
+    let mut result = [0u8;16];
+
+    for c in 0..4 {
+        let column = &state[4*c..4*c+4];
+        let all = column[0] ^ column[1] ^ column[2] ^ column[3];
+
+        for r in 0..4 {
+            result[4*c + r] = column[r] ^ all ^ xtime(column[r] ^ column[(r + 1) % 4]);
+        }
+    }
+
+    result
+}
+
+/// Adds a round key to a state
+pub fn add_round_key(state: &Block, round_key: &Block) -> Block {
+    let mut result = [0u8;16];
+
+    for ((r, &x), &k) in result.iter_mut().zip(state.iter()).zip(round_key.iter()) {
+        *r = x ^ k;
+    }
+
+    result
+}
+
+/// Applies full rounds to a state, each adding one of the given round keys followed by SubBytes,
+/// ShiftRows and MixColumns. The result is the state before the next round key is added
+pub fn rounds(state: &Block, round_keys: &[Block]) -> Block {
+    round_keys.iter().fold(*state, |state, round_key| {
+        mix_columns(&shift_rows(&sub_bytes(&add_round_key(&state, round_key))))
+    })
+}
+
+/// Encrypts a single block with AES, the key size is given by the length of the key
+pub fn encrypt(key: &[u8], plaintext: &Block) -> Block {
+    let round_keys = key_expansion(key);
+    let num_rounds = round_keys.len() - 1;
+
+    let state = rounds(plaintext, &round_keys[..num_rounds-1]);
+    let state = shift_rows(&sub_bytes(&add_round_key(&state, &round_keys[num_rounds-1])));
+
+    add_round_key(&state, &round_keys[num_rounds])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The FIPS-197 key of appendix A.1 and its last round key
+    const KEY: Block = [0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6,
+                        0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c];
+    const LAST_ROUND_KEY: Block = [0xd0, 0x14, 0xf9, 0xa8, 0xc9, 0xee, 0x25, 0x89,
+                                   0xe1, 0x3f, 0x0c, 0xc8, 0xb6, 0x63, 0x0c, 0xa6];
+
+    #[test]
+    fn key_schedule() {
+        assert_eq!(key_expansion(&KEY)[10], LAST_ROUND_KEY);
+        assert_eq!(invert_key_schedule(&LAST_ROUND_KEY), KEY);
+    }
+
+    #[test]
+    fn encrypt_known_answer() {
+        let key: Vec<u8> = (0..16).collect();
+        let plaintext: Vec<u8> = (0..16).map(|i| 0x11 * i).collect();
+        let ciphertext = [0x69, 0xc4, 0xe0, 0xd8, 0x6a, 0x7b, 0x04, 0x30,
+                          0xd8, 0xcd, 0xb7, 0x80, 0x70, 0xb4, 0xc5, 0x5a];
+
+        assert_eq!(encrypt(&key, &block(&plaintext)), ciphertext);
+    }
+}
@@ -0,0 +1,395 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use readers::Trace;
+use simulate::Rng;
+use tables::INV_S;
+use aes;
+
+/// A permutation of the byte values
+type Permutation = [u8;256];
+
+/// Traces whose states only differ in byte `position`, which takes all 256 values. The traces are
+/// indexed by the value of that byte
+pub struct Group {
+    pub position: usize,
+    pub traces: Vec<usize>,
+}
+
+/// A sample that holds an encoded byte Q(y) of the first round, where Q is an unknown bijection
+/// and y depends on the S-box outputs of several input bytes, e.g. a MixColumns output byte
+pub struct EncodedByte {
+    pub sample: usize,
+    pub key_bytes: Vec<(usize, u8)>,
+    pub encoding: Option<(usize, Permutation)>,
+}
+
+/// Generates chosen inputs for the analysis: for each input byte, `groups` groups of 256 inputs
+/// in which only that byte varies, while the other bytes are random
+pub fn chosen_inputs(groups: usize, seed: u64) -> Vec<Vec<u8>> {
+    let mut rng = Rng::new(seed);
+    let mut inputs = vec![];
+
+    for position in 0..16 {
+        for _ in 0..groups {
+            let base: Vec<u8> = (0..16).map(|_| rng.next_u8()).collect();
+
+            for x in 0..256 {
+                let mut input = base.clone();
+                input[position] = x as u8;
+                inputs.push(input);
+            }
+        }
+    }
+
+    inputs
+}
+
+/// Finds the groups of traces in which a single byte of the AES states of the inputs, as given by
+/// `ByteOrder::block`, takes all values
+pub fn find_groups(inputs: &[Vec<u8>]) -> Vec<Group> {
+    let mut groups = vec![];
+
+    for position in 0..inputs[0].len() {
+        let mut candidates: HashMap<Vec<u8>, Vec<Option<usize>>> = HashMap::new();
+
+        for (i, input) in inputs.iter().enumerate() {
+            let mut others = input.clone();
+            others[position] = 0;
+
+            let traces = candidates.entry(others).or_insert_with(|| vec![None;256]);
+            traces[input[position] as usize].get_or_insert(i);
+        }
+
+        for traces in candidates.values() {
+            if traces.iter().all(|t| t.is_some()) {
+                groups.push(Group {
+                    position,
+                    traces: traces.iter().map(|t| t.unwrap()).collect(),
+                });
+            }
+        }
+    }
+
+    groups.sort_by_key(|g| (g.position, g.traces[0]));
+    groups
+}
+
+/// The function of the varying byte that a sample computes within a group, if it is a bijection
+fn group_function(sample: usize, group: &Group, traces: &[Trace]) -> Option<Permutation> {
+    let mut function = [0u8;256];
+    let mut seen = [false;256];
+
+    for (x, &t) in group.traces.iter().enumerate() {
+        let y = traces[t][sample];
+
+        if seen[y as usize] {
+            return None;
+        }
+
+        seen[y as usize] = true;
+        function[x] = y;
+    }
+
+    Some(function)
+}
+
+/// The inverse of a permutation
+fn invert(p: &Permutation) -> Permutation {
+    let mut inverse = [0u8;256];
+
+    for (x, &y) in p.iter().enumerate() {
+        inverse[y as usize] = x as u8;
+    }
+
+    inverse
+}
+
+/// The permutation x -> first(second(x))
+fn compose(first: &Permutation, second: &Permutation) -> Permutation {
+    let mut composed = [0u8;256];
+
+    for (x, y) in composed.iter_mut().enumerate() {
+        *y = first[second[x] as usize];
+    }
+
+    composed
+}
+
+/// Recovers the encoding Q of a sample up to an affine map, from the functions of the varying
+/// byte it computes in each group. Two functions of the same input byte only differ in the value
+/// that the contributions of the fixed bytes add to y, so f ∘ f'^-1 = Q ∘ (⊕ δ) ∘ Q^-1, and these
+/// translations form a group isomorphic to GF(2)^8. Building the group from a basis b_0..b_7 gives
+/// Q̃(x) = (b_0^x_0 ∘ ... ∘ b_7^x_7)(0), which is Q ∘ A for an affine bijection A. Returns None if
+/// the translations do not span the whole group or are inconsistent, i.e. if the sample is not of
+/// this form
+fn affine_class(functions: &Vec<(usize, Permutation)>) -> Option<Permutation> {
+    let mut identity = [0u8;256];
+
+    for (x, y) in identity.iter_mut().enumerate() {
+        *y = x as u8;
+    }
+
+    // The elements of the group spanned so far, indexed by the bits of the basis elements used,
+    // and the index of each element by its image of zero
+    let mut span = vec![identity];
+    let mut index: Vec<Option<usize>> = vec![None;256];
+    index[0] = Some(0);
+
+    for (position, function) in functions {
+        let first = match functions.iter().find(|f| f.0 == *position) {
+            Some(first) => invert(&first.1),
+            None => continue,
+        };
+        let translation = compose(function, &first);
+
+        if compose(&translation, &translation) != identity {
+            return None;
+        }
+
+        match index[translation[0] as usize] {
+            Some(i) if span[i] == translation => continue,
+            Some(_) => return None,
+            None => (),
+        }
+
+        let new: Vec<Permutation> = span.iter().map(|e| compose(e, &translation)).collect();
+
+        for e in new {
+            if index[e[0] as usize].is_some() {
+                return None;
+            }
+
+            index[e[0] as usize] = Some(span.len());
+            span.push(e);
+        }
+    }
+
+    if span.len() != 256 {
+        return None;
+    }
+
+    let mut class = [0u8;256];
+
+    for (x, y) in class.iter_mut().enumerate() {
+        *y = span[x][0];
+    }
+
+    Some(class)
+}
+
+/// Tests if a function of GF(2)^8 is affine, F(u) = F(0) ^ sum of u_b (F(2^b) ^ F(0))
+fn is_affine(function: &Permutation) -> bool {
+    let c = function[0];
+
+    (0..256).all(|u| {
+        let expected = (0..8).filter(|b| (u >> b) & 0x1 == 1)
+                             .fold(c, |acc, b| acc ^ function[1 << b] ^ c);
+        function[u] == expected
+    })
+}
+
+/// Recovers the key byte added to the varying byte of a function z(x) = A(S(x ^ k)) with an
+/// unknown affine A. Only the correct key guess makes u -> z(S^-1(u) ^ k) affine
+fn affine_key(function: &Permutation) -> Option<u8> {
+    (0..256).find(|&k| {
+        let mut composed = [0u8;256];
+
+        for (u, z) in composed.iter_mut().enumerate() {
+            *z = function[(INV_S[u] ^ k as u8) as usize];
+        }
+
+        is_affine(&composed)
+    }).map(|k| k as u8)
+}
+
+/// Finds the samples that hold encoded bytes of the first round, and recovers the key byte of
+/// each input byte they depend on. A sample is analysed if it is a bijection of the varying byte
+/// in each group of at least one input byte, and differs between the groups. Samples holding
+/// the same values in all groups as an earlier sample are skipped
+pub fn analyse_samples(samples: &Vec<usize>,
+                       groups: &[Group],
+                       traces: &[Trace])
+                       -> Vec<EncodedByte> {
+    let mut encoded = vec![];
+    let mut seen = HashSet::new();
+
+    for &sample in samples {
+        let mut functions = vec![];
+
+        for position in 0..16 {
+            let position_groups: Vec<&Group> = groups.iter().filter(|g| g.position == position)
+                                                     .collect();
+            let position_functions: Vec<Option<Permutation>> =
+                position_groups.iter().map(|g| group_function(sample, g, traces)).collect();
+
+            if !position_functions.is_empty() && position_functions.iter().all(|f| f.is_some()) {
+                functions.extend(position_functions.into_iter().map(|f| (position, f.unwrap())));
+            }
+        }
+
+        if functions.is_empty() {
+            continue;
+        }
+
+        let values: Vec<u8> = functions.iter().flat_map(|f| f.1.iter().cloned()).collect();
+
+        if !seen.insert(values) {
+            continue;
+        }
+
+        let class = match affine_class(&functions) {
+            Some(class) => invert(&class),
+            None => continue,
+        };
+
+        let mut key_bytes = vec![];
+
+        for position in 0..16 {
+            if let Some(f) = functions.iter().find(|f| f.0 == position) {
+                if let Some(k) = affine_key(&compose(&class, &f.1)) {
+                    key_bytes.push((position, k));
+                }
+            }
+        }
+
+        if !key_bytes.is_empty() {
+            encoded.push(EncodedByte {
+                sample,
+                key_bytes,
+                encoding: None,
+            });
+        }
+    }
+
+    encoded
+}
+
+/// Combines the key bytes found for each sample by majority vote. Returns the key bytes with the
+/// number of samples that agree with them
+pub fn combine_key_bytes(encoded: &Vec<EncodedByte>) -> Vec<Option<(u8, usize)>> {
+    (0..16).map(|position| {
+        let mut votes = [0usize;256];
+
+        for e in encoded {
+            for &(p, k) in &e.key_bytes {
+                if p == position {
+                    votes[k as usize] += 1;
+                }
+            }
+        }
+
+        (0..256).max_by_key(|&k| votes[k])
+                .filter(|&k| votes[k] > 0)
+                .map(|k| (k as u8, votes[k]))
+    }).collect()
+}
+
+/// Recovers the full encoding of the samples that hold a MixColumns output byte of the first
+/// round, once the key bytes of its column are known. The first round is computed for the state
+/// of each input, and a sample holds output byte j if the map from byte j to the sample is a
+/// bijection
+pub fn recover_encodings(encoded: &mut [EncodedByte],
+                         key: &aes::Block,
+                         traces: &Vec<Trace>,
+                         inputs: &[Vec<u8>]) {
+    let outputs: Vec<aes::Block> = inputs.iter().map(|x| aes::rounds(&aes::block(x), &[*key]))
+                                         .collect();
+
+    for e in encoded.iter_mut() {
+        e.encoding = (0..16).filter_map(|j| {
+            let mut encoding: Vec<Option<u8>> = vec![None;256];
+
+            for (output, trace) in outputs.iter().zip(traces) {
+                match encoding[output[j] as usize] {
+                    Some(y) if y != trace[e.sample] => return None,
+                    _ => encoding[output[j] as usize] = Some(trace[e.sample]),
+                }
+            }
+
+            if encoding.iter().any(|y| y.is_none()) {
+                return None;
+            }
+
+            let mut permutation = [0u8;256];
+
+            for (y, q) in permutation.iter_mut().zip(&encoding) {
+                *y = q.unwrap();
+            }
+
+            let mut values: Vec<u8> = permutation.to_vec();
+            values.sort();
+            values.dedup();
+
+            if values.len() == 256 { Some((j, permutation)) } else { None }
+        }).next();
+    }
+}
+
+/// Writes the recovered encodings to file, one sample per line followed by the MixColumns output
+/// byte and the encoding as 256 bytes in hexadecimal, the encoded value of 0 first
+pub fn write_encodings(path: &str, encoded: &Vec<EncodedByte>) {
+    let file = File::create(path).expect("Could not create file.");
+    let mut writer = BufWriter::new(file);
+
+    for e in encoded {
+        if let Some((j, encoding)) = e.encoding {
+            let hex: Vec<String> = encoding.iter().map(|b| format!("{:02x}", b)).collect();
+            writeln!(writer, "{} {} {}", e.sample, j, hex.join(""))
+                .expect("Could not write encoding.");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A random permutation of the byte values
+    fn random_permutation(rng: &mut Rng) -> Permutation {
+        let mut permutation = [0u8;256];
+
+        for (x, y) in permutation.iter_mut().enumerate() {
+            *y = x as u8;
+        }
+
+        for i in (1..256).rev() {
+            let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+            permutation.swap(i, j);
+        }
+
+        permutation
+    }
+
+    #[test]
+    fn encoded_mix_columns() {
+        let key = aes::key_expansion(&(0..16).collect::<Vec<u8>>())[0];
+        let mut rng = Rng::new(5);
+        let encodings: Vec<Permutation> = (0..16).map(|_| random_permutation(&mut rng)).collect();
+
+        // Sample j holds MixColumns output byte j under its own encoding
+        let states = chosen_inputs(5, 6);
+        let traces: Vec<Trace> = states.iter().map(|x| {
+            let output = aes::rounds(&aes::block(x), &[key]);
+            (0..16).map(|j| encodings[j][output[j] as usize]).collect()
+        }).collect();
+
+        let samples: Vec<usize> = (0..16).collect();
+        let groups = find_groups(&states);
+        let mut encoded = analyse_samples(&samples, &groups, &traces);
+        let key_bytes: Vec<u8> = combine_key_bytes(&encoded).iter().map(|k| k.unwrap().0)
+                                                            .collect();
+
+        assert_eq!(encoded.len(), 16);
+        assert_eq!(key_bytes, key.to_vec());
+
+        recover_encodings(&mut encoded, &key, &traces, &states);
+
+        for e in &encoded {
+            let (j, encoding) = e.encoding.expect("Encoding not recovered");
+
+            assert_eq!(j, e.sample);
+            assert_eq!(encoding.to_vec(), encodings[j].to_vec());
+        }
+    }
+}
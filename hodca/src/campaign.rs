@@ -0,0 +1,267 @@
+use std::cmp;
+use std::fs;
+use serde_json;
+use toml;
+use readers::{Trace, pack_traces, trace_dimensions};
+use regions::{Region, parse_region, read_regions, build_regions};
+use options::{Aggregation, BitSelection, ByteOrder, CorrelationType, DataType, GuessType,
+              LeakageModel, TraceFormat, parse_key};
+
+/// A campaign description as it is written in the configuration file
+#[derive(Deserialize)]
+struct RawConfig {
+    path: String,
+    traces: Option<usize>,
+    length: Option<usize>,
+    key: Option<String>,
+    output_size: Option<usize>,
+    byte_order: Option<String>,
+    campaign: Vec<RawEntry>,
+}
+
+/// A single campaign entry as it is written in the configuration file
+#[derive(Deserialize)]
+struct RawEntry {
+    name: Option<String>,
+    order: usize,
+    window: usize,
+    start: Option<usize>,
+    stop: Option<usize>,
+    region: Option<Vec<String>>,
+    region_file: Option<String>,
+    output_size: Option<usize>,
+    correlation: String,
+    data_type: String,
+    guess: String,
+    model: Option<String>,
+    bits: Option<String>,
+    aggregation: Option<String>,
+}
+
+/// A single attack configuration of a campaign
+pub struct CampaignEntry {
+    pub name: String,
+    pub bounds: (usize,usize),
+    pub regions: Vec<Region>,
+    pub window: usize,
+    pub order: usize,
+    pub output_size: usize,
+    pub correlation_type: CorrelationType,
+    pub data_type: DataType,
+    pub guess_type: GuessType,
+    pub model: LeakageModel,
+    pub bit_selection: BitSelection,
+    pub aggregation: Aggregation,
+}
+
+/// A campaign of attacks that are run sequentially over the same trace set
+pub struct CampaignConfig {
+    pub path: String,
+    pub traces: usize,
+    pub length: usize,
+    pub input_size: usize,
+    pub byte_order: ByteOrder,
+    pub key: Option<[u8;16]>,
+    pub entries: Vec<CampaignEntry>,
+}
+
+/// The key recovered by a single campaign entry
+pub struct CampaignResult {
+    pub name: String,
+    pub key: Vec<usize>,
+}
+
+/// Reads a campaign description from file. Files ending in `.json` are parsed as JSON, all other
+/// files as TOML.
+pub fn read_config(config_path: &str) -> Result<CampaignConfig, String> {
+    let contents = fs::read_to_string(config_path)
+                      .map_err(|e| format!("Could not read {}: {}", config_path, e))?;
+
+    let raw: RawConfig = if config_path.ends_with(".json") {
+        serde_json::from_str(&contents).map_err(|e| format!("Could not parse {}: {}", config_path, e))?
+    } else {
+        toml::from_str(&contents).map_err(|e| format!("Could not parse {}: {}", config_path, e))?
+    };
+
+    let key = match raw.key {
+        Some(key) => Some(parse_key(&key).ok_or(format!("{:?} is not a valid key.", key))?),
+        None => None,
+    };
+
+    if raw.campaign.is_empty() {
+        return Err(format!("{} does not contain any campaign entries.", config_path));
+    }
+
+    // All entries share the inputs, so their targets must use the same block size
+    let guess_types = raw.campaign.iter().map(|e| e.guess.parse())
+                                  .collect::<Result<Vec<GuessType>, String>>()?;
+    let input_size = guess_types[0].input_size();
+
+    if guess_types.iter().any(|g| g.input_size() != input_size) {
+        return Err("All campaign entries must use targets with the same input size.".to_owned());
+    }
+
+    let byte_order: ByteOrder = raw.byte_order.unwrap_or("reversed".to_owned()).parse()?;
+
+    if let (ByteOrder::ColumnMajor, false) = (byte_order, input_size == 16) {
+        return Err("A column-major byte order requires inputs of 16 bytes.".to_owned());
+    }
+
+    let (traces, length) = trace_dimensions(&raw.path, raw.traces, raw.length,
+                                            TraceFormat::Daredevil, input_size)?;
+    let mut entries = vec![];
+
+    for (i, entry) in raw.campaign.into_iter().enumerate() {
+        let bounds = (entry.start.unwrap_or(0), entry.stop.unwrap_or(length));
+        let bounds = (cmp::min(bounds.0, length), cmp::min(bounds.1, length));
+
+        if bounds.0 > bounds.1 {
+            return Err(format!("Start index is larger than stop index in entry {}.", i));
+        }
+
+        let mut byte_regions = vec![];
+
+        for region in entry.region.unwrap_or_default() {
+            byte_regions.push(parse_region(&region)?);
+        }
+
+        if let Some(region_file) = entry.region_file {
+            byte_regions.extend(read_regions(&region_file)?);
+        }
+
+        let data_type: DataType = entry.data_type.parse()?;
+        let correlation_type: CorrelationType = entry.correlation.parse()?;
+        correlation_type.validate(data_type, entry.window, traces)?;
+        let model: LeakageModel = entry.model.unwrap_or("value".to_owned()).parse()?;
+        let bit_selection: BitSelection = entry.bits.unwrap_or("single".to_owned()).parse()?;
+        bit_selection.validate(guess_types[i], model)?;
+        let aggregation: Aggregation = entry.aggregation.unwrap_or("sum".to_owned()).parse()?;
+        aggregation.validate(guess_types[i], model, correlation_type, data_type, bit_selection)?;
+
+        entries.push(CampaignEntry {
+            name: entry.name.unwrap_or(format!("entry {}", i)),
+            bounds,
+            regions: build_regions(bounds, guess_types[i].num_chunks(), &byte_regions, length,
+                                   data_type)?,
+            window: entry.window,
+            order: entry.order,
+            output_size: entry.output_size.or(raw.output_size).unwrap_or(10),
+            correlation_type,
+            data_type,
+            guess_type: guess_types[i],
+            model,
+            bit_selection,
+            aggregation,
+        });
+    }
+
+    Ok(CampaignConfig {
+        path: raw.path,
+        traces,
+        length,
+        input_size,
+        byte_order,
+        key,
+        entries,
+    })
+}
+
+/// Runs all entries of a campaign. The traces must be bit-serialized, the byte representation
+/// is derived from them the first time an entry needs it.
+pub fn run_campaign(config: &CampaignConfig,
+                    traces: &[Trace],
+                    inputs: &[Vec<u8>])
+                    -> Vec<CampaignResult> {
+    let mut byte_traces = None;
+    let mut results = vec![];
+
+    for entry in &config.entries {
+        println!("\n#############################");
+        println!("Campaign entry: {}", entry.name);
+        println!("Order: {}", entry.order);
+        println!("Analysis indices: {} -> {}", entry.bounds.0, entry.bounds.1);
+        println!("Window size: {}", entry.window);
+        println!("Correlation: {:?}", entry.correlation_type);
+        println!("Data type: {:?}", entry.data_type);
+        println!("Target: {:?}", entry.guess_type);
+        println!("Model: {:?}", entry.model);
+        println!("Bits: {:?}", entry.bit_selection);
+        println!("Aggregation: {:?}", entry.aggregation);
+        println!("#############################");
+
+        let entry_traces = match entry.data_type {
+            DataType::Bits  => traces,
+            DataType::Bytes => &*byte_traces.get_or_insert_with(|| pack_traces(traces)),
+        };
+
+        let scores = ::attack_all(&entry.regions, entry.window, entry.order, entry.output_size,
+                               entry.correlation_type, entry.data_type, entry.guess_type,
+                               entry.model, entry.bit_selection, entry.aggregation,
+                               config.byte_order, entry_traces, inputs);
+
+        results.push(CampaignResult {
+            name: entry.name.clone(),
+            key: scores.iter().map(|s| s[0].1).collect(),
+        });
+    }
+
+    results
+}
+
+/// Prints which key chunks (bytes for AES) each campaign entry recovered. If the correct key is
+/// known, bytes are compared against it, otherwise against the most common guess for each chunk
+/// over all entries.
+pub fn print_report(config: &CampaignConfig, results: &Vec<CampaignResult>) {
+    let num_chunks = results.iter().map(|r| r.key.len()).max().unwrap_or(0);
+    let known_key = match config.key {
+        Some(key) if num_chunks == key.len() => Some(key),
+        _ => None,
+    };
+
+    let reference: Vec<usize> = match known_key {
+        Some(key) => key.iter().map(|&k| k as usize).collect(),
+        None => {
+            (0..num_chunks).map(|k| {
+                let mut counters = [0;256];
+
+                for result in results.iter().filter(|r| k < r.key.len()) {
+                    counters[result.key[k]] += 1;
+                }
+
+                (0..256).max_by_key(|&x| (counters[x], cmp::Reverse(x))).unwrap()
+            }).collect()
+        },
+    };
+
+    let width = results.iter().map(|r| r.name.len()).max().unwrap_or(0);
+
+    println!("\n#############################");
+    println!("Campaign report");
+    println!("#############################");
+
+    match known_key {
+        Some(_) => print!("{:width$}  ", "Correct key", width = width),
+        None    => print!("{:width$}  ", "Consensus key", width = width),
+    }
+
+    for k in &reference {
+        print!("{:02x}", k);
+    }
+    println!();
+
+    for result in results {
+        let mut matches = 0;
+        print!("{:width$}  ", result.name, width = width);
+
+        for (k, r) in result.key.iter().zip(reference.iter()) {
+            if k == r {
+                print!("{:02x}", k);
+                matches += 1;
+            } else {
+                print!("..");
+            }
+        }
+
+        println!("  ({}/{})", matches, result.key.len());
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use readers::Trace;
+use regions::{Region, region_samples};
+use options::DataType;
+
+/// A relation k_i ^ k_j = difference between two key bytes, inferred from collisions
+pub struct Relation {
+    pub positions: (usize, usize),
+    pub difference: u8,
+    pub collisions: u32,
+    pub total: u32,
+    pub margin: u32,
+}
+
+/// Groups the traces by the values of their samples within a region
+fn region_classes(samples: &[usize], traces: &[Trace]) -> HashMap<Vec<u8>, Vec<usize>> {
+    let mut classes = HashMap::new();
+
+    for (i, trace) in traces.iter().enumerate() {
+        let values: Vec<u8> = samples.iter().map(|&s| trace[s]).collect();
+        classes.entry(values).or_insert_with(Vec::new).push(i);
+    }
+
+    classes
+}
+
+/// Finds the collisions between the regions of each pair of key bytes and infers the difference of
+/// the key bytes. If the region of byte i in trace a equals the region of byte j in trace b, the
+/// same table T was applied to equal values, so T(p_a_i ^ k_i) = T(p_b_j ^ k_j) and, if T is a
+/// bijection, k_i ^ k_j = p_a_i ^ p_b_j. Each collision votes for a difference, and the relation
+/// is the difference with the most votes. This requires the regions of all key bytes to hold
+/// samples of the same operation, in the same order.
+pub fn find_relations(regions: &[(usize, Region)],
+                      data_type: DataType,
+                      traces: &[Trace],
+                      inputs: &[Vec<u8>])
+                      -> Vec<Relation> {
+    let classes: Vec<HashMap<Vec<u8>, Vec<usize>>> =
+        regions.iter().map(|r| region_classes(&region_samples(&r.1, data_type), traces))
+               .collect();
+
+    let mut relations = vec![];
+
+    for i in 0..regions.len() {
+        for j in (i+1)..regions.len() {
+            let (first, second) = (regions[i].0, regions[j].0);
+            let mut votes = [0u32;256];
+
+            for (values, first_traces) in &classes[i] {
+                if let Some(second_traces) = classes[j].get(values) {
+                    for &a in first_traces {
+                        for &b in second_traces {
+                            votes[(inputs[a][first] ^ inputs[b][second]) as usize] += 1;
+                        }
+                    }
+                }
+            }
+
+            let mut sorted: Vec<(u32, usize)> = votes.iter().cloned().zip(0..256).collect();
+            sorted.sort_by(|x, y| y.cmp(x));
+
+            relations.push(Relation {
+                positions: (first, second),
+                difference: sorted[0].1 as u8,
+                collisions: sorted[0].0,
+                total: votes.iter().sum(),
+                margin: sorted[0].0 - sorted[1].0,
+            });
+        }
+    }
+
+    relations
+}
+
+/// Combines relations into classes of key bytes with a known difference. The relations with the
+/// largest margin are used first (a maximum spanning tree), and relations without a margin are
+/// ignored. Returns for each key byte the byte it is relative to, and their difference, together
+/// with the number of used relations that contradict the result.
+pub fn combine_relations(relations: &[Relation]) -> ([(usize, u8);16], usize) {
+    let mut classes = [(0, 0);16];
+
+    for (k, class) in classes.iter_mut().enumerate() {
+        class.0 = k;
+    }
+
+    let mut sorted: Vec<&Relation> = relations.iter().filter(|r| r.margin > 0).collect();
+    sorted.sort_by_key(|r| Reverse(r.margin));
+
+    let mut contradictions = 0;
+
+    for relation in sorted {
+        let (i, j) = relation.positions;
+        let (root_i, root_j) = (classes[i].0, classes[j].0);
+
+        if root_i == root_j {
+            if classes[i].1 ^ classes[j].1 != relation.difference {
+                contradictions += 1;
+            }
+
+            continue;
+        }
+
+        // Merge the class of j into the class of i: k_j ^ k_root_i = diff_i ^ difference ^ diff_j
+        let offset = classes[i].1 ^ relation.difference ^ classes[j].1;
+
+        for class in classes.iter_mut() {
+            if class.0 == root_j {
+                *class = (root_i, class.1 ^ offset);
+            }
+        }
+    }
+
+    (classes, contradictions)
+}
+
+/// Enumerates all keys that satisfy the combined relations. Each class of key bytes multiplies
+/// the number of candidates by 256, so this is only feasible for a few classes
+pub fn key_candidates(classes: &[(usize, u8);16]) -> Vec<[u8;16]> {
+    let roots: Vec<usize> = (0..16).filter(|&k| classes[k].0 == k).collect();
+
+    if roots.len() > 3 {
+        panic!("[ERROR] key_candidates: too many key candidates to enumerate.");
+    }
+
+    let mut candidates = vec![];
+
+    for n in 0..(1usize << (8 * roots.len())) {
+        let mut key = [0u8;16];
+
+        for k in 0..16 {
+            let root = roots.iter().position(|&r| r == classes[k].0).unwrap();
+            key[k] = ((n >> (8 * root)) as u8) ^ classes[k].1;
+        }
+
+        candidates.push(key);
+    }
+
+    candidates
+}
+
+/// Writes key candidates to a file, one key per line in hexadecimal
+pub fn write_candidates(path: &str, candidates: &Vec<[u8;16]>) {
+    let file = File::create(path).expect("Could not create file.");
+    let mut writer = BufWriter::new(file);
+
+    for key in candidates {
+        let hex: Vec<String> = key.iter().map(|b| format!("{:02x}", b)).collect();
+        writeln!(writer, "{}", hex.join("")).expect("Could not write key.");
+    }
+}
@@ -56,6 +56,109 @@ pub fn double_assisted_pearson<T: ToPrimitive>(
     corr
 }
 
+/// Calculates the difference between the mean of x where y is one and the mean of x where y is
+/// zero, using the sum of x and the number of ones in y. y must only contain zeros and ones
+pub fn assisted_difference_of_means<T: ToPrimitive>(x: &[T], y: &[T], sum_x: f64, ones: f64) -> f64 {
+    if x.len() != y.len() {
+        panic!("[ERROR] assisted_difference_of_means: x and y must have same length.");
+    }
+
+    let n = x.len() as f64;
+
+    // All traces fall in the same partition
+    if ones == 0.0 || ones == n {
+        return 0.0;
+    }
+
+    let mut sum_ones = 0.0;
+
+    for i in 0..x.len() {
+        if y[i].to_u64().unwrap() != 0 {
+            sum_ones += x[i].to_f64().unwrap();
+        }
+    }
+
+    sum_ones / ones - (sum_x - sum_ones) / (n - ones)
+}
+
+/// Calculates Welch's t-statistic between the elements of x labelled zero and the elements of x
+/// labelled one. Labels must only be zeros and ones
+pub fn welch_t_statistic<T: ToPrimitive>(x: &[T], labels: &[u8]) -> f64 {
+    if x.len() != labels.len() {
+        panic!("[ERROR] welch_t_statistic: x and labels must have same length.");
+    }
+
+    let mut sums = [(0.0,0.0,0.0);2];
+
+    for i in 0..x.len() {
+        let v = x[i].to_f64().unwrap();
+        let group = &mut sums[labels[i] as usize];
+        group.0 += v;
+        group.1 += v*v;
+        group.2 += 1.0;
+    }
+
+    if sums[0].2 < 2.0 || sums[1].2 < 2.0 {
+        panic!("[ERROR] welch_t_statistic: each group must contain at least two elements.");
+    }
+
+    // Means and unbiased variances of both groups
+    let (m0, m1) = (sums[0].0 / sums[0].2, sums[1].0 / sums[1].2);
+    let v0 = (sums[0].1 - sums[0].2*m0*m0) / (sums[0].2 - 1.0);
+    let v1 = (sums[1].1 - sums[1].2*m1*m1) / (sums[1].2 - 1.0);
+    let denominator = (v0 / sums[0].2 + v1 / sums[1].2).sqrt();
+
+    if denominator == 0.0 {
+        if m0 == m1 { 0.0 } else { (m0 - m1).signum() * f64::INFINITY }
+    } else {
+        (m0 - m1) / denominator
+    }
+}
+
+/// Calculates Pearson's chi-squared statistic of the contingency table of x and y, testing their
+/// independence. All values must be smaller than 16
+pub fn chi_squared_statistic<T: ToPrimitive>(x: &[T], y: &[T]) -> f64 {
+    if x.len() != y.len() {
+        panic!("[ERROR] chi_squared_statistic: x and y must have same length.");
+    }
+
+    let mut table = [0u32;256];
+    let (mut rows, mut columns) = ([0u32;16], [0u32;16]);
+
+    for i in 0..x.len() {
+        let (a, b) = (x[i].to_usize().unwrap(), y[i].to_usize().unwrap());
+
+        if a > 15 || b > 15 {
+            panic!("[ERROR] chi_squared_statistic: values must be smaller than 16.");
+        }
+
+        table[16*a + b] += 1;
+        rows[a] += 1;
+        columns[b] += 1;
+    }
+
+    // The statistic is n*(sum(O^2/(r*c)) - 1), where O is an observed count and r and c are the
+    // sums of its row and column
+    let n = x.len() as f64;
+    let mut sum = 0.0;
+
+    // Only values that occur contribute, which keeps the table small for bits
+    let occurring_rows: Vec<usize> = (0..16).filter(|&a| rows[a] != 0).collect();
+    let occurring_columns: Vec<usize> = (0..16).filter(|&b| columns[b] != 0).collect();
+
+    for &a in &occurring_rows {
+        for &b in &occurring_columns {
+            let o = table[16*a + b] as f64;
+
+            if o != 0.0 {
+                sum += o * o / (rows[a] as f64 * columns[b] as f64);
+            }
+        }
+    }
+
+    n * (sum - 1.0)
+}
+
 /// Adds the number of times x and y are equal to an existing counter. Can be used to parallelize
 /// calculation of log-likelihood scores for multiple guesses
 pub fn add_loglikelihood_counters<T: ToPrimitive>(
@@ -86,4 +189,111 @@ pub fn loglikelihood_correlation(counters: &Vec<u64>) -> f64 {
     }
 
     return log_likelihood
-}
\ No newline at end of file
+}
+
+/// Calculates the Shannon entropy (in bits) of x, estimating the distribution by counting the
+/// occurrences of each value
+pub fn entropy<T: ToPrimitive>(x: &Vec<T>) -> f64 {
+    let mut counters = [0u64;256];
+
+    for v in x {
+        counters[v.to_usize().unwrap()] += 1;
+    }
+
+    let n = x.len() as f64;
+    let mut h = 0.0;
+
+    for &c in counters.iter() {
+        if c != 0 {
+            let p = c as f64 / n;
+            h -= p * p.log2();
+        }
+    }
+
+    h
+}
+
+/// Calculates the joint Shannon entropy (in bits) of x and y. The pairs are sorted and counted,
+/// which avoids a full 256x256 table of counters
+pub fn joint_entropy<T: ToPrimitive>(x: &[T], y: &[T]) -> f64 {
+    if x.len() != y.len() {
+        panic!("[ERROR] joint_entropy: x and y must have same length.");
+    }
+
+    let mut pairs: Vec<u16> = x.iter()
+                               .zip(y.iter())
+                               .map(|(a,b)| (a.to_u16().unwrap() << 8) | b.to_u16().unwrap())
+                               .collect();
+    pairs.sort_unstable();
+
+    let n = x.len() as f64;
+    let mut h = 0.0;
+    let mut i = 0;
+
+    while i < pairs.len() {
+        let mut j = i + 1;
+
+        while j < pairs.len() && pairs[j] == pairs[i] {
+            j += 1;
+        }
+
+        let p = (j - i) as f64 / n;
+        h -= p * p.log2();
+        i = j;
+    }
+
+    h
+}
+
+/// Calculates the mutual information (in bits) between x and y, estimating the distributions by
+/// counting the occurrences of each value
+pub fn mutual_information<T: ToPrimitive>(x: &Vec<T>, y: &Vec<T>) -> f64 {
+    assisted_mutual_information(x, y, entropy(x), entropy(y))
+}
+
+/// Calculates the mutual information between two vectors, using the entropy of both vectors
+pub fn assisted_mutual_information<T: ToPrimitive>(x: &[T], y: &[T], hx: f64, hy: f64) -> f64 {
+    hx + hy - joint_entropy(x, y)
+}
+
+/// Calculates the signal-to-noise ratio of x when partitioned by the values of y, i.e. the
+/// variance of the class means divided by the mean of the class variances. Classes with a single
+/// element are not used to estimate the noise
+pub fn signal_to_noise<T: ToPrimitive>(x: &[T], y: &[T]) -> f64 {
+    if x.len() != y.len() {
+        panic!("[ERROR] signal_to_noise: x and y must have same length.");
+    }
+
+    let mut sums = [(0.0,0.0,0u64);256];
+
+    for i in 0..x.len() {
+        let (v, c) = (x[i].to_f64().unwrap(), y[i].to_usize().unwrap());
+        sums[c].0 += v;
+        sums[c].1 += v*v;
+        sums[c].2 += 1;
+    }
+
+    let classes: Vec<(f64,f64,u64)> = sums.iter()
+                                          .filter(|s| s.2 != 0)
+                                          .map(|s| {
+                                              let mean = s.0 / (s.2 as f64);
+                                              (mean, s.1 / (s.2 as f64) - mean*mean, s.2)
+                                          })
+                                          .collect();
+    let noise_classes: Vec<f64> = classes.iter().filter(|c| c.2 > 1).map(|c| c.1).collect();
+
+    if noise_classes.is_empty() {
+        return 0.0;
+    }
+
+    let k = classes.len() as f64;
+    let mean = classes.iter().map(|c| c.0).sum::<f64>() / k;
+    let signal = classes.iter().map(|c| (c.0 - mean)*(c.0 - mean)).sum::<f64>() / k;
+    let noise = noise_classes.iter().sum::<f64>() / (noise_classes.len() as f64);
+
+    if noise == 0.0 {
+        if signal == 0.0 { 0.0 } else { f64::INFINITY }
+    } else {
+        signal / noise
+    }
+}
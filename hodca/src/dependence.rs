@@ -0,0 +1,179 @@
+use num_cpus;
+use std::cmp;
+use std::thread;
+use std::sync::mpsc;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use tuple_iterator::{TupleIterator,WindowedTupleIterator};
+use readers::Trace;
+use regions::Region;
+use correlation_functions::{entropy, mutual_information};
+
+/// A tuple of sample indices, the input byte it depends on and the score of the dependency
+pub type Dependency = (Vec<usize>, usize, f64);
+
+/// Scores the dependency of a (combined) sample on each input byte. Each bit of the sample is
+/// compared with each input byte using the mutual information normalised by the entropy of the
+/// bit, and the median over the input bytes is subtracted to remove the estimation bias, which is
+/// large for few traces. A byte scores the largest value over the bits
+fn dependency_scores(x: &[u8], input_bytes: &[Vec<u8>]) -> Vec<f64> {
+    let n = input_bytes.len();
+    let mut scores = vec![0.0;n];
+
+    for b in 0..8 {
+        let bits: Vec<u8> = x.iter().map(|v| (v >> b) & 0x1).collect();
+        let h = entropy(&bits);
+
+        // Constant bits do not depend on anything
+        if h == 0.0 {
+            continue;
+        }
+
+        let mut bit_scores = vec![0.0;n];
+
+        for (p, score) in bit_scores.iter_mut().enumerate() {
+            *score = mutual_information(&bits, &input_bytes[p]) / h;
+        }
+
+        let mut sorted = bit_scores.clone();
+        sorted.sort_by(|x, y| x.partial_cmp(y).expect("Could not sort"));
+        let median = (sorted[(n - 1) / 2] + sorted[n / 2]) / 2.0;
+
+        for p in 0..n {
+            scores[p] = f64::max(scores[p], bit_scores[p] - median);
+        }
+    }
+
+    scores
+}
+
+/// Maps all (windowed) tuples of `samples`, where the samples of a tuple are combined with XOR, to
+/// the input byte they depend on, without a key hypothesis. A tuple is mapped if its score is at
+/// least `threshold` for exactly one input byte. Returns the mapped tuples sorted by decreasing
+/// score, and the number of tuples that depend on several input bytes.
+pub fn input_dependencies(samples: &[usize],
+                          window: usize,
+                          order: usize,
+                          threshold: f64,
+                          traces: &[Trace],
+                          inputs: &[Vec<u8>])
+                          -> (Vec<Dependency>, usize) {
+    let num_traces = traces.len();
+    let input_bytes: Vec<Vec<u8>> = (0..inputs[0].len())
+                                        .map(|p| inputs.iter().map(|x| x[p]).collect())
+                                        .collect();
+
+    let num_threads = num_cpus::get();
+    let (result_tx,result_rx) = mpsc::channel();
+
+    for t in 0..num_threads {
+        let (result_tx, input_bytes, traces, samples) =
+            (result_tx.clone(), input_bytes.clone(), traces.to_vec(), samples.to_vec());
+
+        thread::spawn(move || {
+            let mut mapped = vec![];
+            let mut multiple = 0;
+
+            // Iterate over all tuples of sample positions, but skipping num_threads each time
+            // and having an offset of the current thread index
+            let range_size = samples.len();
+            let time_tuples: Vec<Vec<usize>> = if order == 1 {
+                TupleIterator::new(order, range_size)
+                    .filter(|x| x.iter().sum::<usize>() % num_threads == t)
+                    .collect()
+            } else {
+                WindowedTupleIterator::new(order, range_size, window)
+                    .filter(|x| x.iter().sum::<usize>() % num_threads == t)
+                    .collect()
+            };
+
+            for tuple in time_tuples {
+                let mut ho_trace =  vec![0;num_traces];
+
+                for i in 0..num_traces {
+                    for time_point in &tuple {
+                        ho_trace[i] ^= traces[i][samples[*time_point]];
+                    }
+                }
+
+                let scores = dependency_scores(&ho_trace, &input_bytes);
+                let dependent: Vec<usize> = (0..scores.len()).filter(|&p| scores[p] >= threshold)
+                                                             .collect();
+
+                match dependent.len() {
+                    0 => (),
+                    1 => {
+                        let mut indices: Vec<usize> = tuple.iter().map(|&x| samples[x]).collect();
+                        indices.sort();
+                        mapped.push((indices, dependent[0], scores[dependent[0]]));
+                    },
+                    _ => multiple += 1,
+                }
+            }
+
+            result_tx.send((mapped, multiple)).expect("Thread could not send result");
+        });
+    }
+
+    let mut mapped = vec![];
+    let mut multiple = 0;
+
+    for _ in 0..num_threads {
+        let thread_result = result_rx.recv().expect("Main could not receive result");
+
+        mapped.extend(thread_result.0);
+        multiple += thread_result.1;
+    }
+
+    mapped.sort_by(|x: &Dependency, y: &Dependency| {
+        y.2.partial_cmp(&x.2).expect("Could not sort")
+    });
+
+    (mapped, multiple)
+}
+
+/// Builds a region for each of the `input_size` input bytes from the samples of the tuples mapped
+/// to it. Each sample is extended by `margin` samples on each side, and overlapping ranges are
+/// merged
+pub fn dependency_regions(dependencies: &[Dependency],
+                          margin: usize,
+                          length: usize,
+                          input_size: usize)
+                          -> Vec<Region> {
+    let mut regions = vec![];
+
+    for p in 0..input_size {
+        let mut indices: Vec<usize> = dependencies.iter().filter(|d| d.1 == p)
+                                                  .flat_map(|d| d.0.iter().cloned())
+                                                  .collect();
+        indices.sort();
+        indices.dedup();
+
+        let mut region: Region = vec![];
+
+        for i in indices {
+            let range = (i.saturating_sub(margin), cmp::min(i + margin + 1, length));
+
+            match region.last_mut() {
+                Some(last) if last.1 >= range.0 => last.1 = range.1,
+                _ => region.push(range),
+            }
+        }
+
+        regions.push(region);
+    }
+
+    regions
+}
+
+/// Writes mapped tuples to file, one tuple per line followed by the input byte and the score
+pub fn write_dependencies(path: &str, dependencies: &Vec<Dependency>) {
+    let file = File::create(path).expect("Could not create file.");
+    let mut writer = BufWriter::new(file);
+
+    for (tuple, byte, score) in dependencies {
+        let indices: Vec<String> = tuple.iter().map(|x| x.to_string()).collect();
+        writeln!(writer, "{} {} {:.4}", indices.join(" "), byte, score)
+            .expect("Could not write dependency.");
+    }
+}
@@ -0,0 +1,259 @@
+/// Initial permutation. Bits are numbered from 1, starting at the most significant bit
+static IP: [u8;64] = [
+    58, 50, 42, 34, 26, 18, 10,  2, 60, 52, 44, 36, 28, 20, 12,  4,
+    62, 54, 46, 38, 30, 22, 14,  6, 64, 56, 48, 40, 32, 24, 16,  8,
+    57, 49, 41, 33, 25, 17,  9,  1, 59, 51, 43, 35, 27, 19, 11,  3,
+    61, 53, 45, 37, 29, 21, 13,  5, 63, 55, 47, 39, 31, 23, 15,  7];
+
+/// Final permutation, the inverse of the initial permutation
+static FP: [u8;64] = [
+    40,  8, 48, 16, 56, 24, 64, 32, 39,  7, 47, 15, 55, 23, 63, 31,
+    38,  6, 46, 14, 54, 22, 62, 30, 37,  5, 45, 13, 53, 21, 61, 29,
+    36,  4, 44, 12, 52, 20, 60, 28, 35,  3, 43, 11, 51, 19, 59, 27,
+    34,  2, 42, 10, 50, 18, 58, 26, 33,  1, 41,  9, 49, 17, 57, 25];
+
+/// Expansion of the right half to 48 bits
+static E: [u8;48] = [
+    32,  1,  2,  3,  4,  5,  4,  5,  6,  7,  8,  9,  8,  9, 10, 11,
+    12, 13, 12, 13, 14, 15, 16, 17, 16, 17, 18, 19, 20, 21, 20, 21,
+    22, 23, 24, 25, 24, 25, 26, 27, 28, 29, 28, 29, 30, 31, 32,  1];
+
+/// Permutation of the S-box outputs
+static P: [u8;32] = [
+    16,  7, 20, 21, 29, 12, 28, 17,  1, 15, 23, 26,  5, 18, 31, 10,
+     2,  8, 24, 14, 32, 27,  3,  9, 19, 13, 30,  6, 22, 11,  4, 25];
+
+/// Permuted choice 1, selecting 56 key bits
+static PC1: [u8;56] = [
+    57, 49, 41, 33, 25, 17,  9,  1, 58, 50, 42, 34, 26, 18,
+    10,  2, 59, 51, 43, 35, 27, 19, 11,  3, 60, 52, 44, 36,
+    63, 55, 47, 39, 31, 23, 15,  7, 62, 54, 46, 38, 30, 22,
+    14,  6, 61, 53, 45, 37, 29, 21, 13,  5, 28, 20, 12,  4];
+
+/// Permuted choice 2, selecting the 48 round key bits
+static PC2: [u8;48] = [
+    14, 17, 11, 24,  1,  5,  3, 28, 15,  6, 21, 10,
+    23, 19, 12,  4, 26,  8, 16,  7, 27, 20, 13,  2,
+    41, 52, 31, 37, 47, 55, 30, 40, 51, 45, 33, 48,
+    44, 49, 39, 56, 34, 53, 46, 42, 50, 36, 29, 32];
+
+/// Left rotations of the key halves in each round
+static SHIFTS: [usize;16] = [1, 1, 2, 2, 2, 2, 2, 2, 1, 2, 2, 2, 2, 2, 2, 1];
+
+/// The eight S-boxes, each as four rows of sixteen values
+static SBOXES: [[u8;64];8] = [
+    [14,  4, 13,  1,  2, 15, 11,  8,  3, 10,  6, 12,  5,  9,  0,  7,
+      0, 15,  7,  4, 14,  2, 13,  1, 10,  6, 12, 11,  9,  5,  3,  8,
+      4,  1, 14,  8, 13,  6,  2, 11, 15, 12,  9,  7,  3, 10,  5,  0,
+     15, 12,  8,  2,  4,  9,  1,  7,  5, 11,  3, 14, 10,  0,  6, 13],
+    [15,  1,  8, 14,  6, 11,  3,  4,  9,  7,  2, 13, 12,  0,  5, 10,
+      3, 13,  4,  7, 15,  2,  8, 14, 12,  0,  1, 10,  6,  9, 11,  5,
+      0, 14,  7, 11, 10,  4, 13,  1,  5,  8, 12,  6,  9,  3,  2, 15,
+     13,  8, 10,  1,  3, 15,  4,  2, 11,  6,  7, 12,  0,  5, 14,  9],
+    [10,  0,  9, 14,  6,  3, 15,  5,  1, 13, 12,  7, 11,  4,  2,  8,
+     13,  7,  0,  9,  3,  4,  6, 10,  2,  8,  5, 14, 12, 11, 15,  1,
+     13,  6,  4,  9,  8, 15,  3,  0, 11,  1,  2, 12,  5, 10, 14,  7,
+      1, 10, 13,  0,  6,  9,  8,  7,  4, 15, 14,  3, 11,  5,  2, 12],
+    [ 7, 13, 14,  3,  0,  6,  9, 10,  1,  2,  8,  5, 11, 12,  4, 15,
+     13,  8, 11,  5,  6, 15,  0,  3,  4,  7,  2, 12,  1, 10, 14,  9,
+     10,  6,  9,  0, 12, 11,  7, 13, 15,  1,  3, 14,  5,  2,  8,  4,
+      3, 15,  0,  6, 10,  1, 13,  8,  9,  4,  5, 11, 12,  7,  2, 14],
+    [ 2, 12,  4,  1,  7, 10, 11,  6,  8,  5,  3, 15, 13,  0, 14,  9,
+     14, 11,  2, 12,  4,  7, 13,  1,  5,  0, 15, 10,  3,  9,  8,  6,
+      4,  2,  1, 11, 10, 13,  7,  8, 15,  9, 12,  5,  6,  3,  0, 14,
+     11,  8, 12,  7,  1, 14,  2, 13,  6, 15,  0,  9, 10,  4,  5,  3],
+    [12,  1, 10, 15,  9,  2,  6,  8,  0, 13,  3,  4, 14,  7,  5, 11,
+     10, 15,  4,  2,  7, 12,  9,  5,  6,  1, 13, 14,  0, 11,  3,  8,
+      9, 14, 15,  5,  2,  8, 12,  3,  7,  0,  4, 10,  1, 13, 11,  6,
+      4,  3,  2, 12,  9,  5, 15, 10, 11, 14,  1,  7,  6,  0,  8, 13],
+    [ 4, 11,  2, 14, 15,  0,  8, 13,  3, 12,  9,  7,  5, 10,  6,  1,
+     13,  0, 11,  7,  4,  9,  1, 10, 14,  3,  5, 12,  2, 15,  8,  6,
+      1,  4, 11, 13, 12,  3,  7, 14, 10, 15,  6,  8,  0,  5,  9,  2,
+      6, 11, 13,  8,  1,  4, 10,  7,  9,  5,  0, 15, 14,  2,  3, 12],
+    [13,  2,  8,  4,  6, 15, 11,  1, 10,  9,  3, 14,  5,  0, 12,  7,
+      1, 15, 13,  8, 10,  3,  7,  4, 12,  5,  6, 11,  0, 14,  9,  2,
+      7, 11,  4,  1,  9, 12, 14,  2,  0,  6, 10, 13, 15,  3,  5,  8,
+      2,  1, 14,  7,  4, 10,  8, 13, 15, 12,  9,  0,  3,  5,  6, 11]];
+
+/// Applies a DES permutation table to the `input_bits` least significant bits of a value
+fn permute(input: u64, input_bits: usize, table: &[u8]) -> u64 {
+    table.iter().fold(0, |acc, &p| (acc << 1) | ((input >> (input_bits - p as usize)) & 0x1))
+}
+
+/// Looks up a 6-bit value in S-box `position` (0 is S1). The outer bits select the row
+pub fn sbox(position: usize, x: u8) -> u8 {
+    let row = ((x >> 4) & 0x2) | (x & 0x1);
+    let column = (x >> 1) & 0xf;
+
+    SBOXES[position][(16*row + column) as usize]
+}
+
+/// Converts the 8 bytes of a block, most significant first, into a 64-bit block
+pub fn block(bytes: &[u8]) -> u64 {
+    if bytes.len() != 8 {
+        panic!("[ERROR] block: DES blocks are 8 bytes.");
+    }
+
+    bytes.iter().fold(0, |acc, &x| (acc << 8) | x as u64)
+}
+
+/// Returns the 6 bits of the expanded right half that enter S-box `position`
+fn expanded_chunk(half: u64, position: usize) -> u8 {
+    ((permute(half, 32, &E) >> (42 - 6*position)) & 0x3f) as u8
+}
+
+/// The input of S-box `position` in round 1 before the key is added, from the plaintext
+pub fn round1_chunk(plaintext: u64, position: usize) -> u8 {
+    // The right half is the least significant half after the initial permutation
+    expanded_chunk(permute(plaintext, 64, &IP) & 0xffffffff, position)
+}
+
+/// The input of S-box `position` in round 16 before the key is added, from the ciphertext. The
+/// initial permutation undoes the final permutation, whose input is R16 || L16 with L16 = R15
+pub fn round16_chunk(ciphertext: u64, position: usize) -> u8 {
+    expanded_chunk(permute(ciphertext, 64, &IP) & 0xffffffff, position)
+}
+
+/// The round function: expansion, key addition, S-boxes and permutation
+fn feistel(half: u64, round_key: u64) -> u64 {
+    let x = permute(half, 32, &E) ^ round_key;
+    let mut output = 0;
+
+    for position in 0..8 {
+        output = (output << 4) | sbox(position, ((x >> (42 - 6*position)) & 0x3f) as u8) as u64;
+    }
+
+    permute(output, 32, &P)
+}
+
+/// Derives the 16 round keys of a 64-bit key, including the parity bits
+pub fn key_schedule(key: u64) -> Vec<u64> {
+    let cd = permute(key, 64, &PC1);
+    let (mut c, mut d) = (cd >> 28, cd & 0xfffffff);
+    let mut round_keys = vec![];
+
+    for &shift in SHIFTS.iter() {
+        c = ((c << shift) | (c >> (28 - shift))) & 0xfffffff;
+        d = ((d << shift) | (d >> (28 - shift))) & 0xfffffff;
+        round_keys.push(permute((c << 28) | d, 56, &PC2));
+    }
+
+    round_keys
+}
+
+/// Encrypts a single block with DES
+pub fn encrypt(key: u64, plaintext: u64) -> u64 {
+    let ip = permute(plaintext, 64, &IP);
+    let (mut l, mut r) = (ip >> 32, ip & 0xffffffff);
+
+    for round_key in key_schedule(key) {
+        let t = l ^ feistel(r, round_key);
+        l = r;
+        r = t;
+    }
+
+    permute((r << 32) | l, 64, &FP)
+}
+
+/// Combines the eight 6-bit key chunks into a 48-bit round key
+pub fn round_key(chunks: &[usize]) -> u64 {
+    chunks.iter().fold(0, |acc, &x| (acc << 6) | x as u64)
+}
+
+/// Reconstructs all 64-bit keys (with odd parity) that give `round_key` in `round` (1 to 16).
+/// The round key fixes 48 of the 56 key bits, the remaining 8 give 256 candidates
+pub fn invert_key_schedule(round_key: u64, round: usize) -> Vec<u64> {
+    if !(1..=16).contains(&round) {
+        panic!("[ERROR] invert_key_schedule: invalid round.");
+    }
+
+    // Undo PC2, the bits not selected by PC2 are enumerated
+    let mut cd = 0u64;
+
+    for (i, &p) in PC2.iter().enumerate() {
+        cd |= ((round_key >> (47 - i)) & 0x1) << (56 - p as usize);
+    }
+
+    let unknown: Vec<usize> = (1..57).filter(|p| !PC2.contains(&(*p as u8))).collect();
+    let shift: usize = SHIFTS[..round].iter().sum::<usize>() % 28;
+    let mut keys = vec![];
+
+    for n in 0..(1u64 << unknown.len()) {
+        let mut cd = cd;
+
+        for (i, &p) in unknown.iter().enumerate() {
+            cd |= ((n >> i) & 0x1) << (56 - p);
+        }
+
+        // Undo the rotations of both halves
+        let (c, d) = (cd >> 28, cd & 0xfffffff);
+        let c = ((c >> shift) | (c << (28 - shift))) & 0xfffffff;
+        let d = ((d >> shift) | (d << (28 - shift))) & 0xfffffff;
+        let cd = (c << 28) | d;
+
+        // Undo PC1, then set the parity bit of each byte
+        let mut key = 0u64;
+
+        for (i, &p) in PC1.iter().enumerate() {
+            key |= ((cd >> (55 - i)) & 0x1) << (64 - p as usize);
+        }
+
+        for byte in 0..8 {
+            if ((key >> (8*byte + 1)) & 0x7f).count_ones().is_multiple_of(2) {
+                key |= 1 << (8*byte);
+            }
+        }
+
+        keys.push(key);
+    }
+
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: u64 = 0x133457799bbcdff1;
+    const PLAINTEXT: u64 = 0x0123456789abcdef;
+    const CIPHERTEXT: u64 = 0x85e813540f0ab405;
+
+    #[test]
+    fn encrypt_known_answer() {
+        assert_eq!(encrypt(KEY, PLAINTEXT), CIPHERTEXT);
+    }
+
+    #[test]
+    fn key_schedule_inversion() {
+        let round_keys = key_schedule(KEY);
+
+        assert_eq!(round_keys[0], 0x1b02effc7072);
+        assert_eq!(round_keys[15], 0xcb3d8b0e17f5);
+
+        for &round in &[1, 16] {
+            let candidates = invert_key_schedule(round_keys[round - 1], round);
+
+            assert_eq!(candidates.len(), 256);
+            assert!(candidates.iter()
+                              .all(|&k| key_schedule(k)[round - 1] == round_keys[round - 1]));
+
+            let matching: Vec<u64> = candidates.into_iter()
+                                               .filter(|&k| encrypt(k, PLAINTEXT) == CIPHERTEXT)
+                                               .collect();
+            assert_eq!(matching, vec![KEY]);
+        }
+    }
+
+    #[test]
+    fn round_chunks() {
+        let round_keys = key_schedule(KEY);
+        let chunks: Vec<usize> = (0..8).map(|p| ((round_keys[0] >> (42 - 6*p)) & 0x3f) as usize)
+                                       .collect();
+
+        assert_eq!(round_key(&chunks), round_keys[0]);
+
+        // E(R0) starts with 011110 and E(R15) with 001000 in the worked example of this key
+        assert_eq!(round1_chunk(PLAINTEXT, 0), 0x1e);
+        assert_eq!(round16_chunk(CIPHERTEXT, 0), 0x08);
+    }
+}
@@ -0,0 +1,69 @@
+/// A vector over GF(2), packed into 64 bit words
+pub type BitVector = Vec<u64>;
+
+/// Packs a vector of bits into a bit vector. Only the least significant bit of each element is
+/// used
+pub fn pack_bits(bits: &[u8]) -> BitVector {
+    let mut packed = vec![0;bits.len().div_ceil(64)];
+
+    for (i, &b) in bits.iter().enumerate() {
+        packed[i/64] |= ((b & 0x1) as u64) << (i%64);
+    }
+
+    packed
+}
+
+/// A bit vector with all of the first `len` bits set
+pub fn ones(len: usize) -> BitVector {
+    pack_bits(&vec![1;len])
+}
+
+/// A basis of a subspace of GF(2)^n in echelon form. Each row is stored with its pivot, the
+/// lowest set bit, which is not set in any later row
+pub struct Basis {
+    rows: Vec<(usize, BitVector)>,
+}
+
+impl Basis {
+    /// Creates a basis of the zero subspace
+    pub fn new() -> Basis {
+        Basis {
+            rows: vec![],
+        }
+    }
+
+    /// Reduces a vector by the rows of the basis. The result is zero if and only if the vector
+    /// lies in the subspace
+    pub fn reduce(&self, v: &BitVector) -> BitVector {
+        let mut v = v.clone();
+
+        for (pivot, row) in &self.rows {
+            if (v[pivot/64] >> (pivot%64)) & 0x1 == 1 {
+                for (x, y) in v.iter_mut().zip(row.iter()) {
+                    *x ^= y;
+                }
+            }
+        }
+
+        v
+    }
+
+    /// Tests if a vector lies in the subspace
+    pub fn contains(&self, v: &BitVector) -> bool {
+        self.reduce(v).iter().all(|&x| x == 0)
+    }
+
+    /// Adds a vector to the subspace. Returns false if it was already contained in it
+    pub fn insert(&mut self, v: &BitVector) -> bool {
+        let v = self.reduce(v);
+
+        match v.iter().position(|&x| x != 0) {
+            Some(word) => {
+                let pivot = 64*word + v[word].trailing_zeros() as usize;
+                self.rows.push((pivot, v));
+                true
+            },
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use readers::Trace;
+use correlation_functions::entropy;
+
+/// Summary statistics of a trace set
+pub struct TraceInfo {
+    pub num_traces: usize,
+    pub length: usize,
+    pub constant_samples: usize,
+    pub entropy: Vec<f64>,
+}
+
+/// Calculates the Shannon entropy (in bits) of the values of a single sample across all traces
+pub fn sample_entropy(traces: &[Trace], sample: usize) -> f64 {
+    let values: Vec<u8> = traces.iter().map(|t| t[sample]).collect();
+
+    entropy(&values)
+}
+
+/// Collects summary statistics about a trace set
+pub fn describe_traces(traces: &[Trace]) -> TraceInfo {
+    if traces.is_empty() {
+        panic!("[ERROR] describe_traces: trace set is empty.");
+    }
+
+    // We assume that all trace as the same length
+    let length = traces[0].len();
+    let mut constant_samples = 0;
+    let mut entropy = vec![0.0;length];
+
+    for i in 0..length {
+        if traces.iter().all(|t| t[i] == traces[0][i]) {
+            constant_samples += 1;
+        }
+
+        entropy[i] = sample_entropy(traces, i);
+    }
+
+    TraceInfo {
+        num_traces: traces.len(),
+        length,
+        constant_samples,
+        entropy,
+    }
+}
+
+/// Writes the entropy of each sample to file, one sample per line
+pub fn write_entropy(path: &str, info: &TraceInfo) {
+    let file = File::create(path).expect("Could not create file.");
+    let mut writer = BufWriter::new(file);
+
+    for (i, e) in info.entropy.iter().enumerate() {
+        writeln!(writer, "{} {:.6}", i, e).expect("Could not write entropy.");
+    }
+}
@@ -0,0 +1,76 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use readers::Trace;
+use regions::Region;
+use options::LeakageMetric;
+use correlation_functions::*;
+
+/// Calculates a first order leakage score for each sample with respect to the input byte at
+/// `position`. The variance does not depend on the input, and gives the same scores for all
+/// positions.
+pub fn leakage_scores(position: usize,
+                      metric: LeakageMetric,
+                      traces: &[Trace],
+                      inputs: &[Vec<u8>])
+                      -> Vec<f64> {
+    // We assume that all trace as the same length
+    let length = traces[0].len();
+    let input_bytes: Vec<u8> = inputs.iter().map(|x| x[position]).collect();
+    let n = traces.len() as f64;
+    let mut scores = vec![0.0;length];
+
+    for (i, score) in scores.iter_mut().enumerate() {
+        let column: Vec<u8> = traces.iter().map(|t| t[i]).collect();
+
+        *score = match metric {
+            LeakageMetric::Snr               => signal_to_noise(&column, &input_bytes),
+            LeakageMetric::MutualInformation => mutual_information(&column, &input_bytes),
+            LeakageMetric::Variance          => {
+                let (u, v) = get_auxilliary_values(&column);
+                v/n - (u/n)*(u/n)
+            },
+        };
+    }
+
+    scores
+}
+
+/// Proposes a region from leakage scores. Samples scoring at least `threshold` times the highest
+/// score are selected and extended by `margin` samples on each side, and overlapping ranges are
+/// merged.
+pub fn propose_region(scores: &[f64], threshold: f64, margin: usize) -> Region {
+    let max = scores.iter().cloned().fold(0.0, f64::max);
+    let mut region: Region = vec![];
+
+    // A score of infinity means a perfect dependency. If there is one, only samples with a
+    // perfect dependency are selected
+    for (i, &score) in scores.iter().enumerate() {
+        if score <= 0.0 || score < threshold * max {
+            continue;
+        }
+
+        let range = (i.saturating_sub(margin), std::cmp::min(i + margin + 1, scores.len()));
+
+        match region.last_mut() {
+            Some(last) if last.1 >= range.0 => last.1 = range.1,
+            _ => region.push(range),
+        }
+    }
+
+    region
+}
+
+/// Writes regions to file in the format read by `read_regions`
+pub fn write_regions(path: &str, regions: &[Region]) {
+    let file = File::create(path).expect("Could not create file.");
+    let mut writer = BufWriter::new(file);
+
+    for (k, region) in regions.iter().enumerate() {
+        if region.is_empty() {
+            continue;
+        }
+
+        let ranges: Vec<String> = region.iter().map(|r| format!("{}-{}", r.0, r.1)).collect();
+        writeln!(writer, "{}: {}", k, ranges.join(",")).expect("Could not write region.");
+    }
+}
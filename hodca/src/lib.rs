@@ -4,37 +4,161 @@ extern crate tuple_iterator;
 extern crate time;
 extern crate num;
 extern crate num_cpus;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate toml;
 
 mod tables;
 mod correlation_functions;
 mod score_functions;
+mod gf2;
+pub mod aes;
+pub mod des;
+pub mod sm4;
+pub mod lightweight;
 pub mod readers;
+pub mod writers;
 pub mod options;
-
+pub mod info;
+pub mod preprocess;
+pub mod simulate;
+pub mod campaign;
+pub mod regions;
+pub mod leakage;
+pub mod dependence;
+pub mod tvla;
+pub mod collision;
+pub mod bge;
+
+use std::cmp;
 use std::io::{self,Write};
 use tables::*;
 use readers::Trace;
+use regions::{Region, region_samples, format_region};
 use options::*;
+pub use score_functions::KeyScores;
 use score_functions::*;
 
-/// Generate guesses for values that occur in the DCA trace based on the inputs
+/// Returns the part of an input that the key chunk at `position` is added to. Targets that use
+/// the cipher block find it from the bytes of the block in the byte order
+fn input_chunk(position: usize, x: &[u8], guess_type: GuessType, byte_order: ByteOrder) -> u8 {
+    let block = match guess_type.uses_block() {
+        true  => byte_order.block(x),
+        false => vec![],
+    };
+
+    match guess_type {
+        GuessType::Sbox | GuessType::Inverse => x[position],
+        GuessType::Table(_) | GuessType::TableOutput(_) => x[position],
+        GuessType::MixColumns(_) => block[position],
+        GuessType::InverseSbox | GuessType::InverseMul(_) => block[position],
+        GuessType::TTable(_) => block[position],
+        GuessType::DesFirst => des::round1_chunk(des::block(&block), position),
+        GuessType::DesLast  => des::round16_chunk(des::block(&block), position),
+        GuessType::Sm4 | GuessType::Sm4Linear => {
+            sm4::round_chunk(&sm4::block_words(&block), position)
+        },
+        GuessType::Present => lightweight::present_chunk(lightweight::block(&block), position),
+        GuessType::Gift    => lightweight::gift_chunk(lightweight::block(&block), position),
+        GuessType::Skinny  => lightweight::skinny_chunk(lightweight::block(&block), position),
+    }
+}
+
+/// Returns the input bytes that the prediction for the key chunk at `position` depends on. An
+/// input byte is included if setting one of its bits in the all-zero input changes the part of
+/// the input the key chunk is added to
+pub fn chunk_input_bytes(position: usize, guess_type: GuessType, byte_order: ByteOrder)
+                         -> Vec<usize> {
+    let input_size = guess_type.input_size();
+    let zero = vec![0u8;input_size];
+    let base = input_chunk(position, &zero, guess_type, byte_order);
+
+    let mut bytes: Vec<usize> = (0..input_size).filter(|&j| {
+        (0..8).any(|b| {
+            let mut x = zero.clone();
+            x[j] = 1 << b;
+            input_chunk(position, &x, guess_type, byte_order) != base
+        })
+    }).collect();
+
+    // The S-box outputs of known key bytes are part of the prediction as well, the other bytes
+    // that enter the MixColumns output are numbered as the state
+    if let GuessType::MixColumns(key) = guess_type {
+        let others = aes::mix_columns_target(position).1;
+        bytes.extend(others.iter().filter(|o| key[o.0].is_some())
+                                  .map(|o| byte_order.block_index(o.0, input_size)));
+        bytes.sort();
+    }
+
+    bytes
+}
+
+/// Generate guesses for values that occur in the DCA trace based on the inputs, using the chosen
+/// leakage model. There is a guess for each value of the key chunk at `position`. Predicted values
+/// are up to 32 bits wide, to cover targets such as the SM4 linear transform or AES T-tables
 fn generate_guesses(position: usize, 
-                    inputs: &Vec<Vec<u8>>, 
-                    guess_type: GuessType) 
-                    -> Vec<Vec<u8>> {
-    if position > 15 {
+                    inputs: &[Vec<u8>], 
+                    guess_type: GuessType,
+                    model: LeakageModel,
+                    byte_order: ByteOrder)
+                    -> Vec<Vec<u32>> {
+    if position >= guess_type.num_chunks() {
         panic!("[ERROR] generate_guesses: position is out of bounds.");
     }
 
-    let mut guesses = vec![vec![0;inputs.len()];256];
+    // The part of each input that the key chunk is added to
+    let chunks: Vec<u8> = inputs.iter().map(|x| input_chunk(position, x, guess_type, byte_order))
+                                .collect();
+
+    // The S-box outputs of known key bytes that are added to the predicted MixColumns output
+    let known: Vec<u8> = match guess_type {
+        GuessType::MixColumns(key) => {
+            let others = aes::mix_columns_target(position).1;
+
+            inputs.iter().map(|x| {
+                let state = byte_order.block(x);
+
+                others.iter().fold(0, |acc, &(j, coefficient)| match key[j] {
+                    Some(k) => acc ^ aes::mul(S[(state[j] ^ k) as usize], coefficient),
+                    None    => acc,
+                })
+            }).collect()
+        },
+        _ => vec![],
+    };
+
+    let num_guesses = 1 << guess_type.chunk_bits();
+    let mut guesses = vec![vec![0;inputs.len()];num_guesses];
 
     // For each key guess
-    for k in 0..256 {
+    for (k, guess) in guesses.iter_mut().enumerate() {
         // For each input
-        for i in 0..inputs.len() {
-            guesses[k][i] = match guess_type {
-                GuessType::Sbox    => S[(inputs[i][position] ^ k as u8) as usize],
-                GuessType::Inverse => INV[(inputs[i][position] ^ k as u8) as usize],
+        for (i, g) in guess.iter_mut().enumerate() {
+            let x = chunks[i] ^ k as u8;
+            let value = match &guess_type {
+                GuessType::Sbox                          => S[x as usize] as u32,
+                GuessType::Inverse                       => INV[x as usize] as u32,
+                GuessType::DesFirst | GuessType::DesLast => des::sbox(position, x) as u32,
+                GuessType::Sm4                           => sm4::SBOX[x as usize] as u32,
+                GuessType::Sm4Linear => sm4::linear_contribution(sm4::SBOX[x as usize], position),
+                GuessType::Present                       => lightweight::PRESENT_SBOX[x as usize] as u32,
+                GuessType::Gift                          => lightweight::GIFT_SBOX[x as usize] as u32,
+                GuessType::Skinny                        => lightweight::SKINNY_SBOX[x as usize] as u32,
+                GuessType::Table(table)                  => table[x as usize] as u32,
+                GuessType::TableOutput(table)            => (table[chunks[i] as usize] ^ k as u8) as u32,
+                GuessType::MixColumns(_)                 => (aes::xtime(S[x as usize]) ^ known[i]) as u32,
+                GuessType::InverseSbox                   => INV_S[x as usize] as u32,
+                GuessType::InverseMul(c)                 => aes::mul(INV_S[x as usize], *c) as u32,
+                GuessType::TTable(Some(t))               => aes::t_table(*t, x),
+                // The position is a state index, so its row selects the table
+                GuessType::TTable(None)                  => aes::t_table(position % 4, x),
+            };
+
+            *g = match model {
+                LeakageModel::Value           => value,
+                LeakageModel::HammingWeight   => value.count_ones(),
+                LeakageModel::HammingDistance => (value ^ chunks[i] as u32).count_ones(),
             };
         }
     }
@@ -42,132 +166,338 @@ fn generate_guesses(position: usize,
     guesses
 }
 
+/// Computes the first AES rounds of each input with the known round keys, so the inputs are those
+/// of the round after them. The round keys are in state order
+pub fn aes_round_inputs(inputs: &[Vec<u8>], round_keys: &[aes::Block], byte_order: ByteOrder)
+                        -> Vec<Vec<u8>> {
+    inputs.iter()
+          .map(|x| byte_order.input(&aes::rounds(&aes::block(&byte_order.block(x)), round_keys)))
+          .collect()
+}
+
+/// Combines the key bytes found by `attack_all` for an AES target into a round key in state
+/// order. Targets that do not use the cipher block number the key bytes as the input bytes
+pub fn aes_state_key(key_bytes: &Vec<usize>, guess_type: GuessType, byte_order: ByteOrder)
+                     -> aes::Block {
+    let round_key = aes::round_key(key_bytes);
+
+    match guess_type.uses_block() {
+        true  => round_key,
+        false => aes::block(&byte_order.block(&round_key)),
+    }
+}
+
 /// Extracts a specific bit position of supplied guesses
-fn get_bit_guesses(bit_position: u8, guesses: &Vec<Vec<u8>>) -> Vec<Vec<u8>> {
-    if bit_position > 8 {
+fn get_bit_guesses(bit_position: u8, guesses: &[Vec<u32>]) -> Vec<Vec<u8>> {
+    if bit_position >= 32 {
         panic!("[ERROR] get_bit_guesses: invalid bit position.")
     }
 
-    let mut bit_guesses = vec![vec![0;guesses[0].len()];256];
+    let mut bit_guesses = vec![vec![0;guesses[0].len()];guesses.len()];
 
-    for i in 0..256 {
+    for i in 0..guesses.len() {
         for j in 0..guesses[0].len() {
-            bit_guesses[i][j] = (guesses[i][j] >> bit_position) & 0x1;
+            bit_guesses[i][j] = ((guesses[i][j] >> bit_position) & 0x1) as u8;
         }
     }
 
     bit_guesses
 }
 
+/// Extracts a specific byte position of supplied guesses
+fn get_byte_guesses(byte_position: u8, guesses: &[Vec<u32>]) -> Vec<Vec<u8>> {
+    if byte_position >= 4 {
+        panic!("[ERROR] get_byte_guesses: invalid byte position.")
+    }
+
+    let mut byte_guesses = vec![vec![0;guesses[0].len()];guesses.len()];
+
+    for i in 0..guesses.len() {
+        for j in 0..guesses[0].len() {
+            byte_guesses[i][j] = (guesses[i][j] >> (8 * byte_position)) as u8;
+        }
+    }
+
+    byte_guesses
+}
+
+/// Computes the parity of the bits of supplied guesses selected by a mask
+fn get_mask_guesses(mask: u8, guesses: &[Vec<u32>]) -> Vec<Vec<u8>> {
+    let mut mask_guesses = vec![vec![0;guesses[0].len()];guesses.len()];
+
+    for i in 0..guesses.len() {
+        for j in 0..guesses[0].len() {
+            mask_guesses[i][j] = ((guesses[i][j] & mask as u32).count_ones() & 0x1) as u8;
+        }
+    }
+
+    mask_guesses
+}
+
+/// Combines the scores of the single bits (or bytes, for words) of the guesses into key scores.
+/// Scores are compared in absolute value, and the combined scores are larger for better guesses
+fn aggregate_bit_scores(bit_scores: &[KeyScores], aggregation: Aggregation) -> KeyScores {
+    let num_guesses = bit_scores[0].len();
+    let mut key_scores: KeyScores = (0..num_guesses).map(|i| (0.0, i)).collect();
+
+    for i in 0..num_guesses {
+        let mut scores: Vec<f64> = bit_scores.iter().map(|s| s[i].0.abs()).collect();
+
+        key_scores[i].0 = match aggregation {
+            Aggregation::Sum      => scores.iter().sum(),
+            Aggregation::Max      => scores.iter().cloned().fold(0.0, f64::max),
+            Aggregation::BestK(k) => {
+                scores.sort_by(|x,y| y.partial_cmp(x).expect("Could not sort"));
+                scores.iter().take(k).sum()
+            },
+            Aggregation::Product  => {
+                // Geometric mean of the scores normalised to a probability over the guesses,
+                // scaled such that a uniform distribution gives one
+                let log_sum: f64 = bit_scores.iter().map(|s| {
+                    let total: f64 = s.iter().map(|x| x.0.abs()).sum();
+
+                    // A bit without any signal does not favour any guess
+                    match total == 0.0 {
+                        true  => 0.0,
+                        false => (num_guesses as f64 * s[i].0.abs() / total).ln(),
+                    }
+                }).sum();
+
+                (log_sum / bit_scores.len() as f64).exp()
+            },
+            Aggregation::Rank     => {
+                let ranks: usize = (0..bit_scores.len()).map(|b| bit_rank(&bit_scores[b], i))
+                                                       .sum();
+
+                num_guesses as f64 - ranks as f64 / bit_scores.len() as f64
+            },
+        };
+    }
+
+    key_scores
+}
+
+/// Returns the rank of a guess within unsorted scores, where the best guess has rank zero
+fn bit_rank(scores: &KeyScores, guess: usize) -> usize {
+    scores.iter().filter(|x| x.0.abs() > scores[guess].0.abs()).count()
+}
+
 /// Selects the chosen correlation function and scoring method, and calculates the key scores based
 /// on a set of guesses
-fn calculate_key_scores(bounds: (usize,usize), 
+fn calculate_key_scores(samples: &[usize], 
                         window: usize, 
                         order: usize,
                         correlation_type: CorrelationType, 
-                        traces: &Vec<Trace>, 
-                        guesses: &Vec<Vec<u8>>) 
+                        traces: &[Trace], 
+                        guesses: &[Vec<u8>]) 
                         -> KeyScores {
     match correlation_type {
         CorrelationType::Pearson => {
-            pearson_scores(bounds, window, order, traces, guesses)
+            pearson_scores(samples, window, order, traces, guesses)
         },
         CorrelationType::Equality => {
-            equality_scores(bounds, window, order, traces, guesses)
+            equality_scores(samples, window, order, traces, guesses)
         },
         CorrelationType::Likelihood => {
-            likelihood_scores(bounds, window, order, traces, guesses)
+            likelihood_scores(samples, window, order, traces, guesses)
+        },
+        CorrelationType::MutualInformation => {
+            mutual_information_scores(samples, window, order, traces, guesses)
+        },
+        CorrelationType::DifferenceOfMeans => {
+            difference_of_means_scores(samples, window, order, traces, guesses)
+        },
+        CorrelationType::LinearDecoding => {
+            linear_decoding_scores(samples, window, traces, guesses)
+        },
+        CorrelationType::ChiSquared => {
+            chi_squared_scores(samples, window, order, traces, guesses)
         },
     }
 }
 
 /// Calcuates the key scores for a specific byte position, using the chosen correlation function
-/// and scoring method
+/// and scoring method. Only samples within the region of the byte position are analysed
 fn attack_position(position: usize, 
-                   bounds: (usize,usize), 
+                   region: &Region, 
                    window: usize, 
                    order: usize,
                    output_size: usize,
                    correlation_type: CorrelationType, 
                    data_type: DataType, 
                    guess_type: GuessType,
-                   traces: &Vec<Trace>, 
-                   inputs: &Vec<Vec<u8>>) 
+                   model: LeakageModel,
+                   bit_selection: BitSelection,
+                   aggregation: Aggregation,
+                   byte_order: ByteOrder,
+                   traces: &[Trace], 
+                   inputs: &[Vec<u8>]) 
                    -> KeyScores {
-    let bounds = match data_type {
-        DataType::Bytes => (bounds.0/8, bounds.1/8),
-        _               => bounds,
-    };
+    let samples = region_samples(region, data_type);
 
     // We assume that all trace as the same length. This is true if they are
     // generated with tracergrind + bin2daredevil
-    if bounds.0 > traces[0].len() || bounds.1 > traces[0].len() {
+    if samples.iter().any(|&x| x >= traces[0].len()) {
         panic!("[ERROR] attack_position: start or stop position out of bounds.")
     }
 
+    if samples.is_empty() {
+        panic!("[ERROR] attack_position: region does not contain any samples.")
+    }
+
+    // Tuples are formed over the samples of the region, so the window cannot exceed its size
+    let window = cmp::min(window, samples.len());
+
     // It doesn't make sense to consider a window smaller than the order
     if order != 1 && window < order {
         panic!("[ERROR] attack_position: window cannot be smaller than order");
     }
 
-    let guesses = generate_guesses(position, &inputs,guess_type);
-    let mut key_scores = [(0.0, 0); 256];
+    let guesses = generate_guesses(position, inputs, guess_type, model, byte_order);
+    let num_guesses = guesses.len();
+    let mut key_scores: KeyScores = (0..num_guesses).map(|i| (0.0, i)).collect();
 
-    for i in 0..256 {
-        key_scores[i].1 = i;
+    // The number of bits needed to represent the predicted values
+    let predicted_bits = guess_type.predicted_bits(model) as u8;
+
+    // Linear decoding solves linear systems over the bits of the traces
+    if let (CorrelationType::LinearDecoding, DataType::Bytes) = (correlation_type, data_type) {
+        panic!("[ERROR] attack_position: linear decoding requires the bits data type.");
     }
 
-    match data_type {
-        DataType::Bits => {
-            // For each bit of the target key byte
-            for b in 0..8 {
-                print!("\tAttacking bit {}...",b);
-                io::stdout().flush().expect("Unable to flush stdout");
+    // Difference of means and linear decoding only use a single predicted bit, so the bits of
+    // the guess are always attacked separately
+    let attack_bits = matches!((data_type, correlation_type, bit_selection),
+                               (DataType::Bits, _, _)
+                               | (_, CorrelationType::DifferenceOfMeans, _)
+                               | (_, _, BitSelection::Linear));
 
-                let start = time::precise_time_s();
+    if let (true, BitSelection::Linear) = (attack_bits, bit_selection) {
+        if predicted_bits > 8 {
+            panic!("[ERROR] attack_position: linear combinations require at most 8 predicted bits.");
+        }
 
-                // Extract guess values for the current bit position
-                let bit_guesses = get_bit_guesses(b, &guesses);
+        // Linear encodings hide single bits, so instead attack the parity of each non-zero
+        // linear combination of the bits of the guess
+        let num_masks = (1usize << predicted_bits) - 1;
 
-                // Find the correlations for this bit
-                let mut bit_scores = calculate_key_scores(bounds, window, order,
-                                                          correlation_type,
-                                                          &traces, &bit_guesses);
+        print!("\tAttacking {} linear combinations...", num_masks);
+        io::stdout().flush().expect("Unable to flush stdout");
 
-                // Add them to the correlations for the other bits
-                for i in 0..256 {
-                    key_scores[i].0 += bit_scores[i].0.abs();
+        let start = time::precise_time_s();
+        let mut best_masks = vec![0u8;num_guesses];
+
+        for mask in 1..=(num_masks as u8) {
+            let mask_guesses = get_mask_guesses(mask, &guesses);
+            let mask_scores = calculate_key_scores(&samples, window, order,
+                                                   correlation_type,
+                                                   traces, &mask_guesses);
+
+            // Keep the best combination for each key guess
+            for i in 0..num_guesses {
+                if mask_scores[i].0.abs() > key_scores[i].0.abs() {
+                    key_scores[i].0 = mask_scores[i].0;
+                    best_masks[i] = mask;
                 }
+            }
+        }
 
-                let stop = time::precise_time_s();
+        let stop = time::precise_time_s();
 
-                println!(" Done! ({:.4} seconds)", stop - start);
+        println!(" Done! ({:.4} seconds)", stop - start);
 
-                // Sort bit scores
-                bit_scores.sort_by(|x,y| (y.0).abs().partial_cmp(&(x.0).abs()).expect("Could not sort"));
+        let mut sorted_scores = key_scores.clone();
+        sorted_scores.sort_by(|x,y| (y.0).abs().partial_cmp(&(x.0).abs()).expect("Could not sort"));
 
-                for i in 0..output_size {
-                    println!("\t\t{:02x}, score = {:.4}", bit_scores[i].1, bit_scores[i].0);
-                }
+        for &(score, guess) in sorted_scores.iter().take(output_size) {
+            println!("\t\t{:02x}, score = {:.4}, mask = {:02x}", guess, score, best_masks[guess]);
+        }
+    } else if attack_bits {
+        let mut all_bit_scores = vec![];
+
+        // For each bit of the predicted value
+        for b in 0..predicted_bits {
+            print!("\tAttacking bit {}...",b);
+            io::stdout().flush().expect("Unable to flush stdout");
+
+            let start = time::precise_time_s();
+
+            // Extract guess values for the current bit position
+            let bit_guesses = get_bit_guesses(b, &guesses);
+
+            // Find the correlations for this bit
+            let mut bit_scores = calculate_key_scores(&samples, window, order,
+                                                      correlation_type,
+                                                      traces, &bit_guesses);
+
+            all_bit_scores.push(bit_scores.clone());
+
+            let stop = time::precise_time_s();
+
+            println!(" Done! ({:.4} seconds)", stop - start);
+
+            // Sort bit scores
+            bit_scores.sort_by(|x,y| (y.0).abs().partial_cmp(&(x.0).abs()).expect("Could not sort"));
+
+            for &(score, guess) in bit_scores.iter().take(output_size) {
+                println!("\t\t{:02x}, score = {:.4}", guess, score);
             }
-        },
+        }
+
+        // Combine the scores of the bits, and show how each bit ranks the best guess
+        key_scores = aggregate_bit_scores(&all_bit_scores, aggregation);
+
+        let best = key_scores.iter()
+                             .fold(key_scores[0], |acc, &x| if x.0 > acc.0 { x } else { acc });
+        let ranks: Vec<usize> = all_bit_scores.iter().map(|s| bit_rank(s, best.1)).collect();
 
-        DataType::Bytes => {
-            // Attack whole bytes
-            print!("\tAttacking all bits...");
+        println!("\tPer bit ranks of {:02x}: {:?}, first for {}/{} bits", best.1, ranks,
+                 ranks.iter().filter(|&&r| r == 0).count(), ranks.len());
+    } else if predicted_bits > 8 {
+        // Attack each byte of a predicted word, and combine their scores like those of bits
+        let num_bytes = predicted_bits.div_ceil(8);
+        let mut all_byte_scores = vec![];
+
+        for b in 0..num_bytes {
+            print!("\tAttacking byte {}...",b);
             io::stdout().flush().expect("Unable to flush stdout");
 
             let start = time::precise_time_s();
 
-            // Find the correlations for this byte
-            key_scores = calculate_key_scores(bounds, window, order,
-                                              correlation_type, 
-                                              &traces, &guesses);
+            let byte_guesses = get_byte_guesses(b, &guesses);
+            let mut byte_scores = calculate_key_scores(&samples, window, order,
+                                                       correlation_type,
+                                                       traces, &byte_guesses);
+
+            all_byte_scores.push(byte_scores.clone());
 
             let stop = time::precise_time_s();
 
             println!(" Done! ({:.4} seconds)", stop - start);
+
+            byte_scores.sort_by(|x,y| (y.0).abs().partial_cmp(&(x.0).abs()).expect("Could not sort"));
+
+            for &(score, guess) in byte_scores.iter().take(output_size) {
+                println!("\t\t{:02x}, score = {:.4}", guess, score);
+            }
         }
+
+        key_scores = aggregate_bit_scores(&all_byte_scores, aggregation);
+    } else {
+        // Attack whole bytes
+        print!("\tAttacking all bits...");
+        io::stdout().flush().expect("Unable to flush stdout");
+
+        let start = time::precise_time_s();
+
+        // Find the correlations for this byte
+        key_scores = calculate_key_scores(&samples, window, order,
+                                          correlation_type,
+                                          traces, &get_byte_guesses(0, &guesses));
+
+        let stop = time::precise_time_s();
+
+        println!(" Done! ({:.4} seconds)", stop - start);
     }
     
     // Sort key scores
@@ -176,41 +506,352 @@ fn attack_position(position: usize,
     key_scores
 }
 
-/// Calcuates the key scores for a all byte position, using the chosen correlation function 
-/// and scoring method, returns the highest scoring key bytes
-pub fn attack_all(bounds: (usize,usize), 
+/// Calcuates the key scores for a all key chunks (the bytes for AES) of the target, using the
+/// chosen correlation function and scoring method, returns the scores of each key chunk, sorted
+/// from best to worst. Each chunk is analysed within its own region
+pub fn attack_all(regions: &[Region], 
                   window: usize, 
                   order: usize, 
                   output_size: usize,
                   correlation_type: CorrelationType, 
                   data_type: DataType, 
                   guess_type: GuessType,
-                  traces: &Vec<Trace>, 
-                  inputs: &Vec<Vec<u8>>) 
-                  -> [usize; 16] {
-    let mut full_key = [0;16];
+                  model: LeakageModel,
+                  bit_selection: BitSelection,
+                  aggregation: Aggregation,
+                  byte_order: ByteOrder,
+                  traces: &[Trace], 
+                  inputs: &[Vec<u8>]) 
+                  -> Vec<KeyScores> {
+    if regions.len() != guess_type.num_chunks() {
+        panic!("[ERROR] attack_all: expected a region for each key chunk.");
+    }
+
+    if inputs.iter().any(|x| x.len() != guess_type.input_size()) {
+        panic!("[ERROR] attack_all: inputs do not match the block size of the target.");
+    }
+
+    let chunk = match guess_type.chunk_bits() {
+        8 => "byte",
+        _ => "chunk",
+    };
+    let mut all_scores = vec![];
+
+    for (k, region) in regions.iter().enumerate() {
+        println!("\nAttacking key {} {}...", chunk, k);
+        println!("\tAnalysis indices: {}", format_region(region));
+
+        // The input bytes of the chunk, numbered as in the input file
+        let mut file_bytes: Vec<usize> = chunk_input_bytes(k, guess_type, byte_order)
+                                             .iter()
+                                             .map(|&j| byte_order.file_index(j, inputs[0].len()))
+                                             .collect();
+        file_bytes.sort();
 
-    for k in 0..16 {
-        println!("\nAttacking key byte {}...", k);
+        let file_bytes: Vec<String> = file_bytes.iter().map(|j| j.to_string()).collect();
+        println!("\tInput file bytes: {}", file_bytes.join(", "));
+
+        if let GuessType::MixColumns(_) = guess_type {
+            println!("\tMixColumns output byte: {}", aes::mix_columns_target(k).0);
+        }
 
         let start = time::precise_time_s();
-        let key_scores = attack_position(k, bounds, window, order, output_size,
-                                         correlation_type, data_type, guess_type,
-                                         &traces,&inputs);
+        let key_scores = attack_position(k, region, window, order, output_size,
+                                         correlation_type, data_type, guess_type, model,
+                                         bit_selection, aggregation, byte_order, traces,
+                                         inputs);
         let stop = time::precise_time_s();
 
-        println!("\nFinished attacking key byte {} in {:.4} seconds.", k, stop-start);
+        println!("\nFinished attacking key {} {} in {:.4} seconds.", chunk, k, stop-start);
 
-        for i in 0..output_size {
-            println!("\t{:02x}, score = {:.4}", key_scores[i].1, key_scores[i].0);
+        for &(score, guess) in key_scores.iter().take(output_size) {
+            println!("\t{:02x}, score = {:.4}", guess, score);
         }
             
         println!("");
-        println!("\tLowest score: {:.4}", key_scores[255].0);
+        println!("\tLowest score: {:.4}", key_scores[key_scores.len()-1].0);
         println!("\tHighest score: {:.4}", key_scores[0].0);
 
-        full_key[k] = key_scores[0].1;
+        all_scores.push(key_scores);
+    }
+
+    all_scores
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The inputs read from a file holding `file`, as `read_inputs` numbers them
+    fn read(file: &[u8], byte_order: ByteOrder) -> Vec<u8> {
+        (0..file.len()).map(|i| file[byte_order.file_index(i, file.len())]).collect()
+    }
+
+    /// Attacks noise-free traces with the Pearson correlation on bytes, returns the best guesses
+    fn attack(guess_type: GuessType,
+              byte_order: ByteOrder,
+              traces: &[Trace],
+              inputs: &[Vec<u8>])
+              -> Vec<usize> {
+        let regions = vec![vec![(0, 8*traces[0].len())];guess_type.num_chunks()];
+        let scores = attack_all(&regions, 1, 1, 1, CorrelationType::Pearson, DataType::Bytes,
+                                guess_type, LeakageModel::Value, BitSelection::Single,
+                                Aggregation::Sum, byte_order, traces, inputs);
+
+        scores.iter().map(|s| s[0].1).collect()
+    }
+
+    /// A block stored row by row as a 4x4 matrix, as in a column-major file
+    fn transpose(block: &[u8]) -> Vec<u8> {
+        (0..16).map(|i| block[4*(i % 4) + i / 4]).collect()
+    }
+
+    /// Checks that each byte order gives the chunks of the block stored in its file layout
+    fn check_byte_orders(guess_type: GuessType, block: &[u8], expected: &[u8]) {
+        let mut files = vec![(ByteOrder::Reversed, block.to_vec()),
+                             (ByteOrder::AsIs, block.to_vec())];
+
+        if block.len() == 16 {
+            files.push((ByteOrder::ColumnMajor, transpose(block)));
+        }
+
+        for (byte_order, file) in files {
+            let x = read(&file, byte_order);
+            let chunks: Vec<u8> = (0..guess_type.num_chunks())
+                                      .map(|p| input_chunk(p, &x, guess_type, byte_order))
+                                      .collect();
+
+            assert_eq!(chunks, expected, "{:?}", byte_order);
+        }
+    }
+
+    #[test]
+    fn des_byte_orders() {
+        let block = [0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef];
+        let first: Vec<u8> = (0..8).map(|p| des::round1_chunk(0x0123456789abcdef, p)).collect();
+        let last: Vec<u8> = (0..8).map(|p| des::round16_chunk(0x0123456789abcdef, p)).collect();
+
+        check_byte_orders(GuessType::DesFirst, &block, &first);
+        check_byte_orders(GuessType::DesLast, &block, &last);
+    }
+
+    #[test]
+    fn sm4_byte_orders() {
+        let block: Vec<u8> = (0..16).map(|i| 0x11 * i as u8).collect();
+        let words = [0x00112233, 0x44556677, 0x8899aabb, 0xccddeeff];
+        let expected: Vec<u8> = (0..4).map(|p| sm4::round_chunk(&words, p)).collect();
+
+        check_byte_orders(GuessType::Sm4, &block, &expected);
+        check_byte_orders(GuessType::Sm4Linear, &block, &expected);
+    }
+
+    #[test]
+    fn lightweight_byte_orders() {
+        let block = [0xfe, 0xdc, 0xba, 0x98, 0x76, 0x54, 0x32, 0x10];
+        let x = 0xfedcba9876543210;
+        let present: Vec<u8> = (0..16).map(|p| lightweight::present_chunk(x, p)).collect();
+        let gift: Vec<u8> = (0..16).map(|p| lightweight::gift_chunk(x, p)).collect();
+        let skinny: Vec<u8> = (0..8).map(|p| lightweight::skinny_chunk(x, p)).collect();
+
+        check_byte_orders(GuessType::Present, &block, &present);
+        check_byte_orders(GuessType::Gift, &block, &gift);
+        check_byte_orders(GuessType::Skinny, &block, &skinny);
+    }
+
+    #[test]
+    fn inverse_sbox_key() {
+        // The FIPS-197 key of appendix A.1
+        let key = [0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6,
+                   0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c];
+        let last_round_key = aes::key_expansion(&key)[10];
+        let mut rng = simulate::Rng::new(1);
+        let (mut traces, mut files) = (vec![], vec![]);
+
+        // The ciphertexts are stored first byte first, and the decryption leaks the inverse S-box
+        // outputs of the first round after InvShiftRows
+        for _ in 0..256 {
+            let ciphertext: Vec<u8> = (0..16).map(|_| rng.next_u8()).collect();
+            let state: Vec<u8> = ciphertext.iter().zip(&last_round_key)
+                                           .map(|(&c, &k)| INV_S[(c ^ k) as usize])
+                                           .collect();
+
+            traces.push(aes::shift_rows(&aes::block(&state)).to_vec());
+            files.push(ciphertext);
+        }
+
+        for &byte_order in &[ByteOrder::Reversed, ByteOrder::AsIs] {
+            let inputs: Vec<Vec<u8>> = files.iter().map(|f| read(f, byte_order)).collect();
+            let full_key = attack(GuessType::InverseSbox, byte_order, &traces, &inputs);
+
+            assert_eq!(aes::round_key(&full_key), last_round_key);
+            assert_eq!(aes::invert_key_schedule(&aes::round_key(&full_key)), key);
+        }
+    }
+
+    #[test]
+    fn second_round_key() {
+        // The FIPS-197 key of appendix C.3
+        let key: Vec<u8> = (0..32).collect();
+        let round_keys = aes::key_expansion(&key);
+        let mut rng = simulate::Rng::new(2);
+        let (mut traces, mut files) = (vec![], vec![]);
+
+        // The plaintexts are stored first byte first, and the traces hold the S-box outputs of
+        // the first two rounds
+        for _ in 0..256 {
+            let plaintext: Vec<u8> = (0..16).map(|_| rng.next_u8()).collect();
+            let state = aes::rounds(&aes::block(&plaintext), &round_keys[..1]);
+            let mut trace: Trace = plaintext.iter().zip(&round_keys[0])
+                                            .map(|(&x, &k)| S[(x ^ k) as usize]).collect();
+            trace.extend(state.iter().zip(&round_keys[1]).map(|(&x, &k)| S[(x ^ k) as usize]));
+
+            traces.push(trace);
+            files.push(plaintext);
+        }
+
+        for &byte_order in &[ByteOrder::Reversed, ByteOrder::AsIs, ByteOrder::ColumnMajor] {
+            let file_inputs: Vec<Vec<u8>> = match byte_order {
+                ByteOrder::ColumnMajor => files.iter().map(|f| transpose(f)).collect(),
+                _                      => files.clone(),
+            };
+            let inputs: Vec<Vec<u8>> = file_inputs.iter().map(|f| read(f, byte_order)).collect();
+
+            let first = attack(GuessType::Sbox, byte_order, &traces, &inputs);
+            let mut found = vec![aes_state_key(&first, GuessType::Sbox, byte_order)];
+
+            let inputs = aes_round_inputs(&inputs, &found, byte_order);
+            let second = attack(GuessType::Sbox, byte_order, &traces, &inputs);
+            found.push(aes_state_key(&second, GuessType::Sbox, byte_order));
+
+            assert_eq!(found, round_keys[..2].to_vec());
+
+            let candidates = aes::key_candidates(&found);
+            assert_eq!(candidates[2], ("AES-256", key.clone(), true));
+            assert!(!candidates[0].2 && !candidates[1].2);
+        }
+    }
+
+    #[test]
+    fn mix_columns_prediction() {
+        let key = aes::key_expansion(&(0..16).collect::<Vec<u8>>())[0];
+        let mut rng = simulate::Rng::new(3);
+        let files: Vec<Vec<u8>> = (0..16).map(|_| (0..16).map(|_| rng.next_u8()).collect())
+                                         .collect();
+
+        for &byte_order in &[ByteOrder::Reversed, ByteOrder::AsIs, ByteOrder::ColumnMajor] {
+            let inputs: Vec<Vec<u8>> = files.iter().map(|f| read(f, byte_order)).collect();
+
+            for position in 0..16 {
+                // All other key bytes are known, so the prediction is the whole output byte
+                let mut known: [Option<u8>;16] = [None;16];
+
+                for j in (0..16).filter(|&j| j != position) {
+                    known[j] = Some(key[j]);
+                }
+
+                let guess_type = GuessType::MixColumns(known);
+                let (target, others) = aes::mix_columns_target(position);
+                let guesses = generate_guesses(position, &inputs, guess_type,
+                                               LeakageModel::Value, byte_order);
+
+                for (file, &guess) in files.iter().zip(&guesses[key[position] as usize]) {
+                    let state = match byte_order {
+                        ByteOrder::ColumnMajor => aes::block(&transpose(file)),
+                        _                      => aes::block(file),
+                    };
+                    let output = aes::mix_columns(&aes::shift_rows(&aes::sub_bytes(
+                                     &aes::add_round_key(&state, &key))));
+
+                    assert_eq!(guess, output[target] as u32);
+                }
+
+                // The input file holds the state first byte first, except for column-major
+                let mut expected: Vec<usize> = others.iter().map(|o| o.0).collect();
+                expected.push(position);
+
+                let mut file_bytes: Vec<usize> = expected.iter().map(|&j| match byte_order {
+                    ByteOrder::ColumnMajor => 4*(j % 4) + j / 4,
+                    _                      => j,
+                }).collect();
+                file_bytes.sort();
+
+                let mut found: Vec<usize> = chunk_input_bytes(position, guess_type, byte_order)
+                                                .iter()
+                                                .map(|&j| byte_order.file_index(j, 16))
+                                                .collect();
+                found.sort();
+
+                assert_eq!(found, file_bytes);
+            }
+        }
+    }
+
+    #[test]
+    fn t_table_prediction() {
+        let key = aes::key_expansion(&(0..16).collect::<Vec<u8>>())[0];
+        let mut rng = simulate::Rng::new(4);
+        let files: Vec<Vec<u8>> = (0..16).map(|_| (0..16).map(|_| rng.next_u8()).collect())
+                                         .collect();
+
+        for &byte_order in &[ByteOrder::Reversed, ByteOrder::AsIs, ByteOrder::ColumnMajor] {
+            let inputs: Vec<Vec<u8>> = files.iter().map(|f| read(f, byte_order)).collect();
+
+            for position in 0..16 {
+                let guesses = generate_guesses(position, &inputs, GuessType::TTable(None),
+                                               LeakageModel::Value, byte_order);
+
+                for (file, &guess) in files.iter().zip(&guesses[key[position] as usize]) {
+                    let state = match byte_order {
+                        ByteOrder::ColumnMajor => aes::block(&transpose(file)),
+                        _                      => aes::block(file),
+                    };
+
+                    // The MixColumns output of the column when only this byte is non-zero
+                    let mut active = [0u8;16];
+                    active[position] = S[(state[position] ^ key[position]) as usize];
+
+                    let column = position / 4;
+                    let output = aes::mix_columns(&active);
+                    let word = output[4*column..4*column+4].iter()
+                                                         .fold(0, |acc, &b| (acc << 8) | b as u32);
+
+                    assert_eq!(guess, word);
+                }
+            }
+        }
     }
 
-    full_key
+    #[test]
+    fn product_of_uniform_scores() {
+        // Four bits that do not favour any of the 16 guesses of a 4-bit chunk
+        let bit_scores: Vec<KeyScores> = vec![(0..16).map(|g| (0.5, g)).collect();4];
+
+        for &(score, _) in &aggregate_bit_scores(&bit_scores, Aggregation::Product) {
+            assert!((score - 1.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn table_targets() {
+        let inputs: Vec<Vec<u8>> = (0..16).map(|i| (0..16).map(|j| (17*i + j) as u8).collect())
+                                          .collect();
+        let order = ByteOrder::Reversed;
+
+        for position in 0..16 {
+            let sbox = generate_guesses(position, &inputs, GuessType::Sbox, LeakageModel::Value,
+                                        order);
+            let table = generate_guesses(position, &inputs, GuessType::Table(S),
+                                         LeakageModel::Value, order);
+            let output = generate_guesses(position, &inputs, GuessType::TableOutput(S),
+                                          LeakageModel::Value, order);
+
+            assert_eq!(table, sbox);
+
+            for (k, guesses) in output.iter().enumerate() {
+                for (x, &guess) in inputs.iter().zip(guesses) {
+                    assert_eq!(guess, (S[x[position] as usize] ^ k as u8) as u32);
+                }
+            }
+        }
+    }
 }
\ No newline at end of file
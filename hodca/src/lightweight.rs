@@ -0,0 +1,144 @@
+/// The PRESENT S-box
+pub static PRESENT_SBOX: [u8;16] = [
+    0xc, 0x5, 0x6, 0xb, 0x9, 0x0, 0xa, 0xd, 0x3, 0xe, 0xf, 0x8, 0x4, 0x7, 0x1, 0x2];
+
+/// The GIFT S-box
+pub static GIFT_SBOX: [u8;16] = [
+    0x1, 0xa, 0x4, 0xc, 0x6, 0xf, 0x3, 0x9, 0x2, 0xd, 0xb, 0x7, 0x5, 0x0, 0x8, 0xe];
+
+/// The 4-bit SKINNY S-box
+pub static SKINNY_SBOX: [u8;16] = [
+    0xc, 0x6, 0x9, 0x0, 0x1, 0xa, 0x2, 0xb, 0x3, 0x8, 0x5, 0xd, 0x4, 0xe, 0x7, 0xf];
+
+/// GIFT-64 bit permutation, bit i moves to bit GIFT_PERMUTATION[i]. Bits are numbered from the
+/// least significant bit
+static GIFT_PERMUTATION: [u8;64] = [
+     0, 17, 34, 51, 48,  1, 18, 35, 32, 49,  2, 19, 16, 33, 50,  3,
+     4, 21, 38, 55, 52,  5, 22, 39, 36, 53,  6, 23, 20, 37, 54,  7,
+     8, 25, 42, 59, 56,  9, 26, 43, 40, 57, 10, 27, 24, 41, 58, 11,
+    12, 29, 46, 63, 60, 13, 30, 47, 44, 61, 14, 31, 28, 45, 62, 15];
+
+/// SKINNY ShiftRows, cell i of the output is cell SKINNY_SHIFT_ROWS[i] of the input
+static SKINNY_SHIFT_ROWS: [usize;16] = [0, 1, 2, 3, 7, 4, 5, 6, 10, 11, 8, 9, 13, 14, 15, 12];
+
+/// Converts the 8 bytes of a block, most significant first, into a 64-bit block
+pub fn block(bytes: &[u8]) -> u64 {
+    if bytes.len() != 8 {
+        panic!("[ERROR] block: lightweight cipher blocks are 8 bytes.");
+    }
+
+    bytes.iter().fold(0, |acc, &x| (acc << 8) | x as u64)
+}
+
+/// Returns nibble `position` of a block, nibble 0 being the most significant
+fn nibble(x: u64, position: usize) -> u8 {
+    ((x >> (60 - 4*position)) & 0xf) as u8
+}
+
+/// Applies a 4-bit S-box to every nibble of a block
+fn substitute(x: u64, sbox: &[u8;16]) -> u64 {
+    (0..16).fold(0, |acc, p| (acc << 4) | sbox[nibble(x, p) as usize] as u64)
+}
+
+/// The nibble of the plaintext that key nibble `position` is added to before the first S-box
+/// layer of PRESENT. The key chunks are the 64 most significant bits of the key register
+pub fn present_chunk(block: u64, position: usize) -> u8 {
+    nibble(block, position)
+}
+
+/// The nibble that round key bits are added to before the second S-box layer of GIFT-64, the
+/// first that depends on the key. Each chunk holds bit i of U (as bit 1) and of V (as bit 0) for
+/// nibble i = 15 - `position` of the state
+pub fn gift_chunk(block: u64, position: usize) -> u8 {
+    let x = substitute(block, &GIFT_SBOX);
+    let mut y = (0..64).fold(0u64, |acc, i| acc | ((x >> i) & 0x1) << GIFT_PERMUTATION[i]);
+
+    // The constant of the first round is 1, added to bit 3, and bit 63 is always flipped
+    y ^= 1 << 3 | 1 << 63;
+
+    nibble(y, position)
+}
+
+/// Combines the key chunks found by `attack_all` into the words U and V of the first GIFT-64
+/// round key, which are the words k1 and k0 of the key
+pub fn gift_round_key(key_chunks: &[usize]) -> (u16, u16) {
+    let mut round_key = (0, 0);
+
+    for (position, &k) in key_chunks.iter().enumerate() {
+        let i = 15 - position;
+        round_key.0 |= (((k >> 1) & 0x1) << i) as u16;
+        round_key.1 |= ((k & 0x1) << i) as u16;
+    }
+
+    round_key
+}
+
+/// The nibble that key nibble `position` is added to before the second S-box layer of
+/// SKINNY-64-64, the first that depends on the key. Only the first two rows of the tweakey are
+/// added in the first round, and MixColumns leaves a cell that depends on a single key nibble for
+/// each of them: cell 4 + j for row 0 and cell 8 + (j + 1) mod 4 for row 1. The key chunks are
+/// the 8 most significant nibbles of the key
+pub fn skinny_chunk(block: u64, position: usize) -> u8 {
+    let mut cells: Vec<u8> = (0..16).map(|p| SKINNY_SBOX[nibble(block, p) as usize]).collect();
+
+    // Round constants of the first round
+    cells[0] ^= 0x1;
+    cells[8] ^= 0x2;
+
+    let shifted: Vec<u8> = SKINNY_SHIFT_ROWS.iter().map(|&i| cells[i]).collect();
+
+    match position {
+        0..=3 => shifted[position],
+        4..=7 => shifted[4 + (position - 3) % 4] ^ shifted[8 + (position - 3) % 4],
+        _ => panic!("[ERROR] skinny_chunk: position is out of bounds."),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAINTEXT: u64 = 0x0123456789abcdef;
+
+    /// The nibbles of a hexadecimal string
+    fn nibbles(hex: &str) -> Vec<u8> {
+        hex.chars().map(|c| c.to_digit(16).unwrap() as u8).collect()
+    }
+
+    #[test]
+    fn present_first_round() {
+        // The 64 most significant bits of the 80-bit key 0f1e2d3c4b5a69788796
+        let key = nibbles("0f1e2d3c4b5a6978");
+        let outputs: Vec<u8> = (0..16).map(|p| PRESENT_SBOX[(present_chunk(PLAINTEXT, p)
+                                                              ^ key[p]) as usize])
+                                      .collect();
+
+        assert_eq!(outputs, nibbles("c1b7a3084625f9ed"));
+    }
+
+    #[test]
+    fn gift_second_round() {
+        // The key 00112233445566778899aabbccddeeff has U = ccdd and V = eeff
+        let (u, v): (usize, usize) = (0xccdd, 0xeeff);
+        let key: Vec<usize> = (0..16).map(|p| 15 - p)
+                                     .map(|i| ((u >> i) & 0x1) << 1 | (v >> i) & 0x1)
+                                     .collect();
+        let outputs: Vec<u8> = (0..16).map(|p| GIFT_SBOX[(gift_chunk(PLAINTEXT, p)
+                                                           ^ key[p] as u8) as usize])
+                                      .collect();
+
+        assert_eq!(outputs, nibbles("a51591858771c637"));
+        assert_eq!(gift_round_key(&key), (0xccdd, 0xeeff));
+    }
+
+    #[test]
+    fn skinny_second_round() {
+        // The first two rows of the key f5269826fc681238
+        let key = nibbles("f5269826");
+        let outputs: Vec<u8> = (0..8).map(|p| SKINNY_SBOX[(skinny_chunk(PLAINTEXT, p)
+                                                            ^ key[p]) as usize])
+                                     .collect();
+
+        assert_eq!(outputs, nibbles("90d2a033"));
+    }
+}
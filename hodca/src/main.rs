@@ -2,95 +2,1145 @@ extern crate structopt;
 extern crate hodca;
 extern crate time;
 
-use hodca::readers::{read_traces,read_inputs};
-use hodca::options::{InputArgs, GuessType, CorrelationType, DataType};
+use hodca::readers::{read_traces,read_trace_set,read_inputs,read_labels,trace_dimensions};
+use hodca::readers::INPUT_SIZE;
+use hodca::des;
+use hodca::sm4;
+use hodca::aes;
+use hodca::lightweight;
+use hodca::writers::{write_traces,write_inputs};
+use hodca::options::{Command, TraceArgs, AttackArgs, CampaignArgs, RoiArgs, MapArgs, TvlaArgs,
+                     CollideArgs, BgeArgs, InfoArgs, ConvertArgs, PreprocessArgs, SimulateArgs,
+                     ChosenArgs};
+use hodca::options::{GuessType, CorrelationType, DataType, TraceFormat, LeakageMetric, ByteOrder};
+use hodca::options::LeakageModel;
+use hodca::options::{BitSelection, Aggregation};
+use hodca::options::parse_key;
+use hodca::info::{describe_traces,write_entropy};
+use hodca::preprocess::{select_samples,filter_traces};
+use hodca::simulate::simulate_traces;
+use hodca::regions::{Region,parse_region,read_regions,build_regions,format_region,region_samples};
+use hodca::leakage::{leakage_scores,propose_region,write_regions};
+use hodca::dependence::{input_dependencies,dependency_regions,write_dependencies};
+use hodca::tvla::{welch_t_test,write_statistics};
+use hodca::collision::{find_relations,combine_relations,key_candidates,write_candidates};
+use hodca::campaign::{read_config,run_campaign,print_report};
+use hodca::bge::{chosen_inputs,find_groups,analyse_samples,combine_key_bytes,recover_encodings,
+                 write_encodings};
 use structopt::StructOpt;
 use std::cmp;
 
 fn main() {
-    let options = InputArgs::from_args();
+    match Command::from_args() {
+        Command::Attack(options)     => attack(options),
+        Command::Campaign(options)   => campaign(options),
+        Command::Roi(options)        => roi(options),
+        Command::Map(options)        => map(options),
+        Command::Tvla(options)       => tvla(options),
+        Command::Collide(options)    => collide(options),
+        Command::Bge(options)        => bge(options),
+        Command::Info(options)       => info(options),
+        Command::Convert(options)    => convert(options),
+        Command::Preprocess(options) => preprocess(options),
+        Command::Simulate(options)   => simulate(options),
+        Command::Chosen(options)     => chosen(options),
+    }
+}
 
+fn attack(options: AttackArgs) {
     // Parse options
-    let path = options.path;
-    let num_traces = options.traces;
-    let trace_length = options.length;
-    let order = options.order;
-    let bounds = (options.start.unwrap_or(0), options.stop.unwrap_or(trace_length));
-    let bounds = (cmp::min(bounds.0, trace_length), cmp::min(bounds.1, trace_length));
+    let path = &options.trace_args.path;
+    let guess_type: GuessType = match options.guess.parse() {
+        Ok(guess_type) => guess_type,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let (input_size, byte_order, num_traces, trace_length, bounds) =
+        match trace_set(&options.trace_args, guess_type.input_size(), TraceFormat::Daredevil,
+                        options.start, options.stop) {
+            Ok(trace_set) => trace_set,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        };
 
-    if bounds.0 > bounds.1 {
-        println!("Start index is larger than stop index.");
+    if input_size != guess_type.input_size() {
+        println!("The {:?} target uses inputs of {} bytes.", guess_type, guess_type.input_size());
         return;
     }
 
+    let order = options.order;
+    let byte_regions = match key_regions(&options.region, &options.region_file) {
+        Ok(byte_regions) => byte_regions,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
     let window = options.window;
     let output_size = options.output_size.unwrap_or(10);
-    let correlation_type = match options.correlation.as_ref() {
-        "pearson"    => CorrelationType::Pearson,
-        "equality"   => CorrelationType::Equality,
-        "likelihood" => CorrelationType::Likelihood,
-        _ => {
-            println!("{:?} is not a valid correlation type.", options.correlation);
+    let correlation_type: CorrelationType = match options.correlation.parse() {
+        Ok(correlation_type) => correlation_type,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let data_type: DataType = match options.data_type.parse() {
+        Ok(data_type) => data_type,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+
+    if let Err(e) = correlation_type.validate(data_type, window, num_traces) {
+        println!("{}", e);
+        return;
+    }
+
+    let regions = match build_regions(bounds, guess_type.num_chunks(), &byte_regions,
+                                      trace_length, data_type) {
+        Ok(regions) => regions,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let model: LeakageModel = match options.model.unwrap_or("value".to_owned()).parse() {
+        Ok(model) => model,
+        Err(e) => {
+            println!("{}", e);
             return;
         }
     };
-    let data_type = match options.data_type.as_ref() {
-        "bits"  => DataType::Bits,
-        "bytes" => DataType::Bytes,
-        _ => {
-            println!("{:?} is not a valid data type.", options.correlation);
+    let bit_selection: BitSelection = match options.bits.unwrap_or("single".to_owned()).parse() {
+        Ok(bit_selection) => bit_selection,
+        Err(e) => {
+            println!("{}", e);
             return;
         }
     };
-    let guess_type = match options.guess.as_ref() {
-        "sbox"    => GuessType::Sbox,
-        "inverse" => GuessType::Inverse,
-        _ => {
-            println!("{:?} is not a valid guess type.", options.correlation);
+
+    if let Err(e) = bit_selection.validate(guess_type, model) {
+        println!("{}", e);
+        return;
+    }
+
+    let aggregation: Aggregation = match options.aggregation.unwrap_or("sum".to_owned()).parse() {
+        Ok(aggregation) => aggregation,
+        Err(e) => {
+            println!("{}", e);
             return;
         }
     };
 
-    
+    if let Err(e) = aggregation.validate(guess_type, model, correlation_type, data_type,
+                                           bit_selection) {
+        println!("{}", e);
+        return;
+    }
+    let round_key_list: Vec<String> = match options.round_keys {
+        Some(ref round_keys) => round_keys.split(',').map(|x| x.to_owned()).collect(),
+        None => vec![],
+    };
+    let mut sm4_round_keys: Vec<u32> = vec![];
+    let mut aes_round_keys: Vec<aes::Block> = vec![];
+
+    match guess_type {
+        GuessType::Sm4 | GuessType::Sm4Linear => {
+            for round_key in &round_key_list {
+                match u32::from_str_radix(round_key, 16) {
+                    Ok(round_key) => sm4_round_keys.push(round_key),
+                    Err(_) => {
+                        println!("{:?} is not a valid SM4 round key.", round_key);
+                        return;
+                    }
+                }
+            }
+
+            if sm4_round_keys.len() > 3 {
+                println!("The key schedule only needs four round keys.");
+                return;
+            }
+        },
+        GuessType::Sbox | GuessType::TTable(_) => {
+            for round_key in &round_key_list {
+                match parse_key(round_key) {
+                    Some(round_key) => aes_round_keys.push(round_key),
+                    None => {
+                        println!("{:?} is not a valid AES round key.", round_key);
+                        return;
+                    }
+                }
+            }
+
+            if aes_round_keys.len() > 1 {
+                println!("The key schedule only needs two round keys.");
+                return;
+            }
+        },
+        _ if !round_key_list.is_empty() => {
+            println!("Round keys are only used for AES and SM4 targets.");
+            return;
+        },
+        _ => (),
+    }
+
+
     // Print attack info
     println!("#############################");
     println!("Order: {}", order);
     println!("Traces: {}", num_traces);
     println!("Analysis indices: {} -> {}", bounds.0, bounds.1);
+
+    if !byte_regions.is_empty() {
+        println!("Key bytes with own regions: {}", byte_regions.len());
+    }
+
     println!("Window size: {}", window);
     println!("Correlation: {:?}", correlation_type);
     println!("Data type: {:?}", data_type);
     println!("Target: {:?}", guess_type);
+    println!("Model: {:?}", model);
+    println!("Bits: {:?}", bit_selection);
+    println!("Aggregation: {:?}", aggregation);
+    println!("Byte order: {:?}", byte_order);
+
+    if !round_key_list.is_empty() {
+        println!("Known round keys: {}", round_key_list.len());
+    }
+
     println!("#############################\n");
 
-    
+
     // Read data
     let trace_path = &(path.to_owned() + ".trace");
     let input_path = &(path.to_owned() + ".input");
-    
+
     let start = time::precise_time_ns();
     let traces = read_traces(trace_path, num_traces, trace_length, data_type);
     let stop = time::precise_time_ns();
     println!("Read trace file in {:.4} seconds.",(stop-start) as f64 / 1000000000.0);
 
     let start = time::precise_time_ns();
-    let inputs = read_inputs(input_path, num_traces);
+    let mut inputs = read_inputs(input_path, num_traces, input_size, byte_order);
     let stop = time::precise_time_ns();
     println!("Read input file in {:.4} seconds.",(stop-start) as f64 / 1000000000.0);
 
-    
+    // The rounds with known keys are computed, so the inputs are those of the attacked round
+    if !sm4_round_keys.is_empty() {
+        inputs = inputs.iter().map(|x| {
+            let state = sm4::rounds(&sm4::block_words(&byte_order.block(x)), &sm4_round_keys);
+            byte_order.input(&sm4::block_bytes(&state))
+        }).collect();
+    }
+
+    if !aes_round_keys.is_empty() {
+        inputs = hodca::aes_round_inputs(&inputs, &aes_round_keys, byte_order);
+    }
+
+
     // Start the attack
     let start = time::precise_time_ns();
-    let full_key = hodca::attack_all(bounds, window, order, output_size,
-                                     correlation_type, data_type, guess_type,
-                                     &traces, &inputs);
+    let all_scores = hodca::attack_all(&regions, window, order, output_size,
+                                     correlation_type, data_type, guess_type, model,
+                                     bit_selection, aggregation, byte_order, &traces, &inputs);
     let stop = time::precise_time_ns();
 
     println!("\nAttacked all keys in {} seconds.",(stop-start) as f64 / 1000000000.0 );
+    let full_key: Vec<usize> = all_scores.iter().map(|s| s[0].1).collect();
     println!("Most likely key:");
 
+    // Each key chunk is printed with as many hexadecimal digits as it needs
+    let digits = guess_type.chunk_bits().div_ceil(4);
+
     for k in &full_key {
-      print!("{:02x}",k);
+      print!("{:0width$x}",k, width = digits);
     }
     println!("");
-}
\ No newline at end of file
+
+    if let GuessType::Gift = guess_type {
+        let (u, v) = lightweight::gift_round_key(&full_key);
+        println!("Round 1 key: U = {:04x}, V = {:04x}", u, v);
+        return;
+    }
+
+    if let GuessType::Sbox | GuessType::TTable(_) = guess_type {
+        if !aes_round_keys.is_empty() || options.known_pair.is_some() {
+            let mut round_keys = aes_round_keys;
+            round_keys.push(hodca::aes_state_key(&full_key, guess_type, byte_order));
+            aes_key(&round_keys, options.known_pair);
+        }
+
+        return;
+    }
+
+    // Decryption attacks find the last round key, from which the AES-128 key is reconstructed
+    if let GuessType::InverseSbox | GuessType::InverseMul(_) = guess_type {
+        let key = aes::invert_key_schedule(&aes::round_key(&full_key));
+        println!("Last round key: {}", hex(&aes::round_key(&full_key)));
+        aes_key(&[key], options.known_pair);
+        return;
+    }
+
+    if let GuessType::Sm4 | GuessType::Sm4Linear = guess_type {
+        let mut round_keys = sm4_round_keys;
+        round_keys.push(sm4::round_key(&full_key));
+        sm4_key(&round_keys, options.known_pair);
+        return;
+    }
+
+    // The key chunks of DES form a round key, from which the key schedule is inverted
+    let round = match guess_type {
+        GuessType::DesFirst => 1,
+        GuessType::DesLast  => 16,
+        _ => return,
+    };
+    let round_key = des::round_key(&full_key);
+
+    println!("Round {} key: {:012x}", round, round_key);
+
+    // Some guesses cannot be told apart by the scores, e.g. guesses for S4 whose predicted
+    // bits are equal or complementary, so all combinations of tied guesses are candidates
+    let mut tied: Vec<Vec<usize>> = all_scores.iter()
+                                              .map(|s| s.iter()
+                                                        .filter(|x| x.0.abs() == s[0].0.abs())
+                                                        .map(|x| x.1).collect())
+                                              .collect();
+    let combinations: usize = tied.iter().map(|t| t.len()).product();
+
+    if combinations > 4096 {
+        println!("Too many round keys with tied scores ({}), using the best guesses only.",
+                 combinations);
+        tied = full_key.iter().map(|&k| vec![k]).collect();
+    }
+
+    let mut round_keys: Vec<Vec<usize>> = vec![vec![]];
+
+    for chunk in &tied {
+        round_keys = round_keys.iter()
+                               .flat_map(|r| chunk.iter().map(move |&g| {
+                                   let mut r = r.clone();
+                                   r.push(g);
+                                   r
+                               }))
+                               .collect();
+    }
+
+    if round_keys.len() > 1 {
+        println!("Round keys with tied scores: {}", round_keys.len());
+    }
+
+    let mut candidates: Vec<u64> = round_keys.iter()
+                                             .flat_map(|r| des::invert_key_schedule(
+                                                 des::round_key(r), round))
+                                             .collect();
+
+    if let Some(known_pair) = options.known_pair {
+        let pair: Vec<Option<u64>> = known_pair.split(':')
+                                               .map(|x| u64::from_str_radix(x, 16).ok())
+                                               .collect();
+
+        match pair.as_slice() {
+            [Some(plaintext), Some(ciphertext)] => {
+                candidates.retain(|&key| des::encrypt(key, *plaintext) == *ciphertext);
+            },
+            _ => {
+                println!("{:?} is not a valid known pair.", known_pair);
+                return;
+            },
+        }
+    }
+
+    println!("Key candidates from the key schedule: {}", candidates.len());
+
+    for key in candidates.iter().take(output_size) {
+        println!("\t{:016x}", key);
+    }
+}
+
+/// The size and byte order of the inputs, the number and length of the traces, and the bounds
+/// of the analysed samples
+type TraceSet = (usize, ByteOrder, usize, usize, (usize, usize));
+
+/// Parses the options shared by the commands reading a trace set. The analysed samples are
+/// those from `start` to `stop`, clamped to the trace length, and the input size defaults to
+/// `default_size`
+fn trace_set(trace_args: &TraceArgs,
+             default_size: usize,
+             format: TraceFormat,
+             start: Option<usize>,
+             stop: Option<usize>)
+             -> Result<TraceSet, String> {
+    let input_size = trace_args.input_size.unwrap_or(default_size);
+    let byte_order: ByteOrder = match trace_args.byte_order {
+        Some(ref byte_order) => byte_order.parse()?,
+        None => ByteOrder::Reversed,
+    };
+
+    if input_size == 0 {
+        return Err("The input size must be positive.".to_owned());
+    }
+
+    if let (ByteOrder::ColumnMajor, false) = (byte_order, input_size == 16) {
+        return Err("A column-major byte order requires inputs of 16 bytes.".to_owned());
+    }
+
+    let (num_traces, trace_length) = trace_dimensions(&trace_args.path, trace_args.traces,
+                                                      trace_args.length, format, input_size)?;
+    let bounds = (start.unwrap_or(0), stop.unwrap_or(trace_length));
+    let bounds = (cmp::min(bounds.0, trace_length), cmp::min(bounds.1, trace_length));
+
+    if bounds.0 > bounds.1 {
+        return Err("Start index is larger than stop index.".to_owned());
+    }
+
+    Ok((input_size, byte_order, num_traces, trace_length, bounds))
+}
+
+/// Parses the regions of the key bytes given with --region and read from --region_file
+fn key_regions(region: &[String], region_file: &Option<String>)
+               -> Result<Vec<(usize, Region)>, String> {
+    let mut byte_regions = vec![];
+
+    for region in region {
+        byte_regions.push(parse_region(region)?);
+    }
+
+    if let Some(ref region_file) = *region_file {
+        byte_regions.extend(read_regions(region_file)?);
+    }
+
+    Ok(byte_regions)
+}
+
+/// Formats bytes in hexadecimal
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Reports the AES keys that give the round keys recovered so far. A key is marked if its key
+/// schedule does not give the later round keys
+fn aes_key(round_keys: &[aes::Block], known_pair: Option<String>) {
+    if round_keys.len() > 1 {
+        println!("Round 1 key: {}", hex(&round_keys[1]));
+    }
+
+    let candidates = aes::key_candidates(round_keys);
+
+    for &(name, ref key, consistent) in &candidates {
+        match consistent {
+            true  => println!("{} key: {}", name, hex(key)),
+            false => println!("{} key: {} (does not match the key schedule)", name, hex(key)),
+        }
+    }
+
+    if let Some(known_pair) = known_pair {
+        let pair: Vec<Option<[u8;16]>> = known_pair.split(':').map(parse_key).collect();
+
+        match pair.as_slice() {
+            [Some(plaintext), Some(ciphertext)] => {
+                let matches: Vec<&str> = candidates.iter()
+                                                   .filter(|c| aes::encrypt(&c.1, plaintext)
+                                                               == *ciphertext)
+                                                   .map(|c| c.0)
+                                                   .collect();
+                println!("Keys matching the known pair: {:?}", matches);
+            },
+            _ => println!("{:?} is not a valid known pair.", known_pair),
+        }
+    }
+}
+
+/// Reports the SM4 round keys recovered so far, and the key once four round keys are known
+fn sm4_key(round_keys: &[u32], known_pair: Option<String>) {
+    let round = round_keys.len();
+
+    println!("Round {} key: {:08x}", round, round_keys[round - 1]);
+
+    if round < 4 {
+        let known: Vec<String> = round_keys.iter().map(|k| format!("{:08x}", k)).collect();
+        println!("Attack round {} with --round_keys {}", round + 1, known.join(","));
+        return;
+    }
+
+    let key = sm4::invert_key_schedule(&[round_keys[0], round_keys[1], round_keys[2],
+                                         round_keys[3]]);
+
+    println!("Key from the key schedule: {:08x}{:08x}{:08x}{:08x}", key[0], key[1], key[2],
+             key[3]);
+
+    if let Some(known_pair) = known_pair {
+        let pair: Vec<Option<[u8;16]>> = known_pair.split(':').map(parse_key).collect();
+
+        match pair.as_slice() {
+            [Some(plaintext), Some(ciphertext)] => {
+                let matches = sm4::encrypt(&key, &sm4::block_words(plaintext))
+                              == sm4::block_words(ciphertext);
+                println!("Key matches the known pair: {}", matches);
+            },
+            _ => println!("{:?} is not a valid known pair.", known_pair),
+        }
+    }
+}
+
+fn campaign(options: CampaignArgs) {
+    let config = match read_config(&options.config) {
+        Ok(config) => config,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+
+    println!("#############################");
+    println!("Campaign: {}", options.config);
+    println!("Traces: {}", config.traces);
+    println!("Byte order: {:?}", config.byte_order);
+    println!("Entries: {}", config.entries.len());
+    println!("#############################\n");
+
+    // Read data once, the byte representation is derived from the bits when needed
+    let trace_path = &(config.path.to_owned() + ".trace");
+    let input_path = &(config.path.to_owned() + ".input");
+
+    let traces = read_traces(trace_path, config.traces, config.length, DataType::Bits);
+    let inputs = read_inputs(input_path, config.traces, config.input_size, config.byte_order);
+
+    let start = time::precise_time_ns();
+    let results = run_campaign(&config, &traces, &inputs);
+    let stop = time::precise_time_ns();
+
+    println!("\nFinished campaign in {} seconds.",(stop-start) as f64 / 1000000000.0 );
+    print_report(&config, &results);
+}
+
+fn roi(options: RoiArgs) {
+    let path = &options.trace_args.path;
+    let (input_size, byte_order, num_traces, trace_length, _) =
+        match trace_set(&options.trace_args, INPUT_SIZE, TraceFormat::Daredevil, None, None) {
+            Ok(trace_set) => trace_set,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        };
+    let data_type: DataType = match options.data_type.parse() {
+        Ok(data_type) => data_type,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let metric: LeakageMetric = match options.metric.parse() {
+        Ok(metric) => metric,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let threshold = options.threshold.unwrap_or(0.5);
+    let margin = options.margin.unwrap_or(8);
+
+    println!("#############################");
+    println!("Traces: {}", num_traces);
+    println!("Metric: {:?}", metric);
+    println!("Data type: {:?}", data_type);
+    println!("Threshold: {}", threshold);
+    println!("Margin: {}", margin);
+    println!("Byte order: {:?}", byte_order);
+    println!("#############################\n");
+
+    let traces = read_traces(&(path.to_owned() + ".trace"), num_traces, trace_length, data_type);
+    let inputs = read_inputs(&(path.to_owned() + ".input"), num_traces, input_size, byte_order);
+
+    let mut regions = vec![];
+    let mut window = 1;
+
+    for k in 0..input_size {
+        let scores = leakage_scores(k, metric, &traces, &inputs);
+        let region = propose_region(&scores, threshold, margin);
+        let (peak, peak_score) = scores.iter().cloned().enumerate()
+                                       .fold((0, 0.0), |acc, x| if x.1 > acc.1 { x } else { acc });
+
+        // The proposed window is the size of the largest range, in samples of the data type
+        for range in &region {
+            window = cmp::max(window, range.1 - range.0);
+        }
+
+        // Regions are always given in bits
+        let region: Vec<(usize,usize)> = match data_type {
+            DataType::Bytes => region.iter().map(|r| (8*r.0, 8*r.1)).collect(),
+            DataType::Bits  => region,
+        };
+
+        println!("Key byte {} (input file byte {}): peak score {:.4} at sample {}", k,
+                 byte_order.file_index(k, input_size), peak_score, peak);
+        println!("\tProposed region: {}", format_region(&region));
+
+        regions.push(region);
+    }
+
+    println!("\nProposed window size: {}", window);
+
+    if let Some(output) = options.output {
+        write_regions(&output, &regions);
+        println!("Wrote regions to {}. Use them with --region_file {}.", output, output);
+    }
+}
+
+fn map(options: MapArgs) {
+    let path = &options.trace_args.path;
+    let (input_size, byte_order, num_traces, trace_length, bounds) =
+        match trace_set(&options.trace_args, INPUT_SIZE, TraceFormat::Daredevil,
+                        options.start, options.stop) {
+            Ok(trace_set) => trace_set,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        };
+    let data_type: DataType = match options.data_type.parse() {
+        Ok(data_type) => data_type,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let order = options.order.unwrap_or(1);
+    let window = options.window.unwrap_or(order);
+    let threshold = options.threshold.unwrap_or(0.5);
+    let margin = options.margin.unwrap_or(8);
+    let output_size = options.output_size.unwrap_or(10);
+
+    if order != 1 && window < order {
+        println!("Window cannot be smaller than order.");
+        return;
+    }
+
+    let samples = region_samples(&vec![bounds], data_type);
+    let window = cmp::min(window, samples.len());
+
+    println!("#############################");
+    println!("Order: {}", order);
+    println!("Traces: {}", num_traces);
+    println!("Analysis indices: {} -> {}", bounds.0, bounds.1);
+    println!("Window size: {}", window);
+    println!("Data type: {:?}", data_type);
+    println!("Threshold: {}", threshold);
+    println!("Byte order: {:?}", byte_order);
+    println!("#############################\n");
+
+    let traces = read_traces(&(path.to_owned() + ".trace"), num_traces, trace_length, data_type);
+    let inputs = read_inputs(&(path.to_owned() + ".input"), num_traces, input_size, byte_order);
+
+    let start = time::precise_time_ns();
+    let (mapped, multiple) = input_dependencies(&samples, window, order, threshold,
+                                                &traces, &inputs);
+    let stop = time::precise_time_ns();
+
+    println!("Mapped samples in {:.4} seconds.",(stop-start) as f64 / 1000000000.0);
+    println!("Samples or tuples depending on a single input byte: {}", mapped.len());
+    println!("Samples or tuples depending on several input bytes: {}", multiple);
+
+    for (tuple, byte, score) in mapped.iter().take(output_size) {
+        println!("\t{:?} -> byte {}, score = {:.4}", tuple, byte, score);
+    }
+
+    let length = samples.last().map_or(0, |&s| s + 1);
+
+    // Regions are always given in bits
+    let regions: Vec<Vec<(usize,usize)>> = match data_type {
+        DataType::Bytes => dependency_regions(&mapped, margin, length, input_size)
+                               .iter()
+                               .map(|r| r.iter().map(|x| (8*x.0, 8*x.1)).collect())
+                               .collect(),
+        DataType::Bits  => dependency_regions(&mapped, margin, length, input_size),
+    };
+
+    println!();
+
+    for (k, region) in regions.iter().enumerate() {
+        println!("Input byte {} (input file byte {}): {} mapped, region {}", k,
+                 byte_order.file_index(k, input_size), mapped.iter().filter(|d| d.1 == k).count(),
+                 format_region(region));
+    }
+
+    if let Some(output) = options.output {
+        write_dependencies(&output, &mapped);
+        println!("Wrote mapped samples to {}.", output);
+    }
+
+    if let Some(region_output) = options.region_output {
+        write_regions(&region_output, &regions);
+        println!("Wrote regions to {}. Use them with --region_file {}.", region_output,
+                 region_output);
+    }
+}
+
+fn tvla(options: TvlaArgs) {
+    let path = &options.trace_args.path;
+    let (input_size, _, num_traces, trace_length, bounds) =
+        match trace_set(&options.trace_args, INPUT_SIZE, TraceFormat::Daredevil,
+                        options.start, options.stop) {
+            Ok(trace_set) => trace_set,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        };
+    let data_type: DataType = match options.data_type.parse() {
+        Ok(data_type) => data_type,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let order = options.order.unwrap_or(1);
+    let window = options.window.unwrap_or(order);
+    let threshold = options.threshold.unwrap_or(4.5);
+    let output_size = options.output_size.unwrap_or(10);
+
+    if order != 1 && window < order {
+        println!("Window cannot be smaller than order.");
+        return;
+    }
+
+    let mut traces = read_traces(&(path.to_owned() + ".trace"), num_traces, trace_length,
+                                 data_type);
+
+    // The groups are either two trace sets, or given by a label for each trace
+    let labels = match (options.other, options.labels) {
+        (Some(other), _) => {
+            let (other_traces, _) = match trace_dimensions(&other, None, Some(trace_length),
+                                                           TraceFormat::Daredevil, input_size) {
+                Ok(dimensions) => dimensions,
+                Err(e) => {
+                    println!("{}", e);
+                    return;
+                }
+            };
+
+            traces.extend(read_traces(&(other.to_owned() + ".trace"), other_traces,
+                                      trace_length, data_type));
+
+            let mut labels = vec![0;num_traces];
+            labels.extend(vec![1;other_traces]);
+            labels
+        },
+        (None, Some(labels)) => read_labels(&labels, num_traces),
+        (None, None) => {
+            println!("Either a second trace set or a label file is required.");
+            return;
+        }
+    };
+
+    // The variance of each group is estimated, which needs at least two traces
+    let group_sizes = (labels.iter().filter(|&&x| x == 0).count(),
+                       labels.iter().filter(|&&x| x == 1).count());
+
+    if group_sizes.0 < 2 || group_sizes.1 < 2 {
+        println!("Each group needs at least two traces, found {} / {}.", group_sizes.0,
+                 group_sizes.1);
+        return;
+    }
+
+    let samples = region_samples(&vec![bounds], data_type);
+    let window = cmp::min(window, samples.len());
+
+    println!("#############################");
+    println!("Order: {}", order);
+    println!("Traces: {} / {}", group_sizes.0, group_sizes.1);
+    println!("Analysis indices: {} -> {}", bounds.0, bounds.1);
+    println!("Window size: {}", window);
+    println!("Data type: {:?}", data_type);
+    println!("Threshold: {}", threshold);
+    println!("#############################\n");
+
+    let start = time::precise_time_ns();
+    let (leaking, max_statistic) = welch_t_test(&samples, window, order, threshold,
+                                                &traces, &labels);
+    let stop = time::precise_time_ns();
+
+    println!("Computed t-statistics in {:.4} seconds.",(stop-start) as f64 / 1000000000.0);
+    println!("Largest absolute t-statistic: {:.4}", max_statistic);
+    println!("Samples or tuples exceeding the threshold: {}", leaking.len());
+
+    for (tuple, statistic) in leaking.iter().take(output_size) {
+        println!("\t{:?}, t = {:.4}", tuple, statistic);
+    }
+
+    if let Some(output) = options.output {
+        write_statistics(&output, &leaking);
+        println!("Wrote leaking samples to {}.", output);
+    }
+}
+
+fn collide(options: CollideArgs) {
+    let path = &options.trace_args.path;
+    let (input_size, byte_order, num_traces, trace_length, _) =
+        match trace_set(&options.trace_args, INPUT_SIZE, TraceFormat::Daredevil, None, None) {
+            Ok(trace_set) => trace_set,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        };
+    let data_type: DataType = match options.data_type.parse() {
+        Ok(data_type) => data_type,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let byte_regions = match key_regions(&options.region, &options.region_file) {
+        Ok(byte_regions) => byte_regions,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+
+    // The relations are between the bytes of an AES key
+    if input_size != 16 {
+        println!("Collisions are only found between the bytes of 16 byte inputs.");
+        return;
+    }
+
+    if let Some(region) = byte_regions.iter().find(|r| r.0 >= 16) {
+        println!("Key byte {} is out of range.", region.0);
+        return;
+    }
+
+    // A key byte colliding with itself would only yield k_i ^ k_i
+    let mut seen = [false;16];
+
+    for region in &byte_regions {
+        if seen[region.0] {
+            println!("Key byte {} has more than one region.", region.0);
+            return;
+        }
+
+        seen[region.0] = true;
+    }
+
+    // Collisions are only meaningful between regions holding the same operation
+    if byte_regions.len() < 2 {
+        println!("Regions of at least two key bytes are required.");
+        return;
+    }
+
+    let sizes: Vec<usize> = byte_regions.iter().map(|r| region_samples(&r.1, data_type).len())
+                                        .collect();
+
+    if sizes.iter().any(|&s| s != sizes[0] || s == 0) {
+        println!("All regions must contain the same, non-zero number of samples.");
+        return;
+    }
+
+    if byte_regions.iter().any(|r| r.1.iter().any(|range| range.1 > trace_length)) {
+        println!("Region out of bounds of the traces.");
+        return;
+    }
+
+    println!("#############################");
+    println!("Traces: {}", num_traces);
+    println!("Key bytes with regions: {}", byte_regions.len());
+    println!("Samples per region: {}", sizes[0]);
+    println!("Data type: {:?}", data_type);
+    println!("Byte order: {:?}", byte_order);
+    println!("#############################\n");
+
+    let traces = read_traces(&(path.to_owned() + ".trace"), num_traces, trace_length, data_type);
+    let inputs = read_inputs(&(path.to_owned() + ".input"), num_traces, input_size, byte_order);
+
+    let start = time::precise_time_ns();
+    let relations = find_relations(&byte_regions, data_type, &traces, &inputs);
+    let stop = time::precise_time_ns();
+
+    println!("Found relations in {:.4} seconds.",(stop-start) as f64 / 1000000000.0);
+
+    for relation in &relations {
+        println!("\tk{} ^ k{} = {:02x}, collisions = {} / {}, margin = {}",
+                 relation.positions.0, relation.positions.1, relation.difference,
+                 relation.collisions, relation.total, relation.margin);
+    }
+
+    let (classes, contradictions) = combine_relations(&relations);
+    let roots: Vec<usize> = (0..16).filter(|&k| classes[k].0 == k).collect();
+
+    println!("\nContradicting relations: {}", contradictions);
+    println!("Key bytes relative to a free byte:");
+
+    for &root in &roots {
+        let members: Vec<String> = (0..16).filter(|&k| classes[k].0 == root)
+                                          .map(|k| match k == root {
+                                              true  => format!("k{}", k),
+                                              false => format!("k{} = k{} ^ {:02x}", k, root,
+                                                               classes[k].1),
+                                          })
+                                          .collect();
+        println!("\t{}", members.join(", "));
+    }
+
+    println!("Key candidates: 2^{}", 8 * roots.len());
+
+    if let Some(output) = options.output {
+        if roots.len() > 2 {
+            println!("Too many key candidates to write to {}.", output);
+            return;
+        }
+
+        write_candidates(&output, &key_candidates(&classes));
+        println!("Wrote key candidates to {}.", output);
+    }
+}
+
+fn bge(options: BgeArgs) {
+    let path = &options.trace_args.path;
+    let (input_size, byte_order, num_traces, trace_length, bounds) =
+        match trace_set(&options.trace_args, INPUT_SIZE, TraceFormat::Daredevil,
+                        options.start, options.stop) {
+            Ok(trace_set) => trace_set,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        };
+
+    if input_size != 16 {
+        println!("The analysis uses AES inputs of 16 bytes.");
+        return;
+    }
+
+    let output_size = options.output_size.unwrap_or(10);
+
+    // The encoded values are bytes written to memory
+    let traces = read_traces(&(path.to_owned() + ".trace"), num_traces, trace_length,
+                             DataType::Bytes);
+    let inputs = read_inputs(&(path.to_owned() + ".input"), num_traces, input_size, byte_order);
+    let samples = region_samples(&vec![bounds], DataType::Bytes);
+
+    // The groups, key bytes and encodings are found on the AES states of the inputs
+    let inputs: Vec<Vec<u8>> = inputs.iter().map(|x| byte_order.block(x)).collect();
+    let groups = find_groups(&inputs);
+
+    println!("#############################");
+    println!("Traces: {}", num_traces);
+    println!("Groups with a single varying state byte: {}", groups.len());
+    println!("Analysis indices: {} -> {}", bounds.0, bounds.1);
+    println!("Byte order: {:?}", byte_order);
+    println!("#############################\n");
+
+    if groups.is_empty() {
+        println!("No group of traces in which a single state byte takes all values. Write chosen \
+                  inputs with hodca chosen.");
+        return;
+    }
+
+    let start = time::precise_time_ns();
+    let mut encoded = analyse_samples(&samples, &groups, &traces);
+    let stop = time::precise_time_ns();
+
+    println!("Analysed samples in {:.4} seconds.",(stop-start) as f64 / 1000000000.0);
+    println!("Samples holding encoded bytes: {}", encoded.len());
+
+    let key_bytes = combine_key_bytes(&encoded);
+
+    for (position, key_byte) in key_bytes.iter().enumerate() {
+        match key_byte {
+            Some((k, votes)) => println!("\tKey byte {}: {:02x} ({} of {} samples)", position, k,
+                                         votes, encoded.iter()
+                                                       .filter(|e| e.key_bytes.iter()
+                                                                    .any(|b| b.0 == position))
+                                                       .count()),
+            None => println!("\tKey byte {}: not found", position),
+        }
+    }
+
+    if key_bytes.iter().any(|k| k.is_none()) {
+        return;
+    }
+
+    let mut key = [0u8;16];
+
+    for (byte, key_byte) in key.iter_mut().zip(&key_bytes) {
+        *byte = key_byte.unwrap().0;
+    }
+
+    println!();
+    aes_key(&[key], options.known_pair);
+
+    recover_encodings(&mut encoded, &key, &traces, &inputs);
+
+    println!("\nSamples with a recovered encoding: {}",
+             encoded.iter().filter(|e| e.encoding.is_some()).count());
+
+    for e in encoded.iter().take(output_size) {
+        let positions: Vec<String> = e.key_bytes.iter().map(|b| b.0.to_string()).collect();
+
+        match e.encoding {
+            Some((j, _)) => println!("\tSample {} (state bytes {}): MixColumns output byte {}",
+                                     e.sample, positions.join(", "), j),
+            None => println!("\tSample {} (state bytes {}): encoding known up to an affine map",
+                             e.sample, positions.join(", ")),
+        }
+    }
+
+    if let Some(output) = options.output {
+        write_encodings(&output, &encoded);
+        println!("Wrote encodings to {}.", output);
+    }
+}
+
+fn info(options: InfoArgs) {
+    let path = &options.trace_args.path;
+    let (input_size, byte_order, num_traces, trace_length, _) =
+        match trace_set(&options.trace_args, INPUT_SIZE, TraceFormat::Daredevil, None, None) {
+            Ok(trace_set) => trace_set,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        };
+    let data_type: DataType = match options.data_type.parse() {
+        Ok(data_type) => data_type,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+
+    let trace_path = &(path.to_owned() + ".trace");
+    let input_path = &(path.to_owned() + ".input");
+
+    let traces = read_traces(trace_path, num_traces, trace_length, data_type);
+    let inputs = read_inputs(input_path, num_traces, input_size, byte_order);
+    let info = describe_traces(&traces);
+
+    let entropy_min = info.entropy.iter().cloned().fold(f64::INFINITY, f64::min);
+    let entropy_max = info.entropy.iter().cloned().fold(0.0, f64::max);
+    let entropy_mean = info.entropy.iter().sum::<f64>() / (info.length as f64);
+
+    println!("#############################");
+    println!("Traces: {}", info.num_traces);
+    println!("Inputs: {} of {} bytes", inputs.len(), input_size);
+    println!("Samples per trace: {}", info.length);
+    println!("Data type: {:?}", data_type);
+    println!("Constant samples: {} ({:.4} %)", info.constant_samples,
+             100.0 * (info.constant_samples as f64) / (info.length as f64));
+    println!("Entropy per sample (min/mean/max): {:.4} / {:.4} / {:.4}",
+             entropy_min, entropy_mean, entropy_max);
+    println!("#############################");
+
+    if let Some(output) = options.output {
+        write_entropy(&output, &info);
+        println!("Wrote sample entropy to {}.", output);
+    }
+}
+
+fn convert(options: ConvertArgs) {
+    let path = &options.trace_args.path;
+    let from: TraceFormat = match options.from.parse() {
+        Ok(format) => format,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let to: TraceFormat = match options.to.parse() {
+        Ok(format) => format,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let (input_size, byte_order, num_traces, trace_length, _) =
+        match trace_set(&options.trace_args, INPUT_SIZE, from, None, None) {
+            Ok(trace_set) => trace_set,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        };
+    let traces = read_trace_set(&(path.to_owned() + ".trace"), num_traces, trace_length, from);
+    let inputs = read_inputs(&(path.to_owned() + ".input"), num_traces, input_size, byte_order);
+
+    write_traces(&(options.output.to_owned() + ".trace"), &traces, to);
+    write_inputs(&(options.output.to_owned() + ".input"), &inputs, byte_order);
+
+    println!("Converted {} traces from {:?} to {:?}.", num_traces, from, to);
+}
+
+fn preprocess(options: PreprocessArgs) {
+    let path = &options.trace_args.path;
+    let (input_size, byte_order, num_traces, trace_length, bounds) =
+        match trace_set(&options.trace_args, INPUT_SIZE, TraceFormat::Daredevil,
+                        options.start, options.stop) {
+            Ok(trace_set) => trace_set,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        };
+
+    let traces = read_traces(&(path.to_owned() + ".trace"), num_traces, trace_length,
+                             DataType::Bits);
+    let inputs = read_inputs(&(path.to_owned() + ".input"), num_traces, input_size, byte_order);
+
+    let samples = select_samples(bounds, options.remove_constant, options.deduplicate, &traces);
+    let traces = filter_traces(&samples, &traces);
+
+    write_traces(&(options.output.to_owned() + ".trace"), &traces, TraceFormat::Daredevil);
+    write_inputs(&(options.output.to_owned() + ".input"), &inputs, byte_order);
+
+    println!("Kept {} of {} samples. New trace length: {}",
+             samples.len(), bounds.1 - bounds.0, samples.len());
+}
+
+fn simulate(options: SimulateArgs) {
+    if options.traces == 0 {
+        println!("The number of traces must be positive.");
+        return;
+    }
+
+    let key = match parse_key(&options.key) {
+        Some(key) => key,
+        None => {
+            println!("{:?} is not a valid key.", options.key);
+            return;
+        }
+    };
+    let masking_order = options.masking_order.unwrap_or(0);
+    let noise = options.noise.unwrap_or(0);
+    let seed = options.seed.unwrap_or(time::precise_time_ns());
+
+    let (traces, inputs) = simulate_traces(options.traces, &key, masking_order, noise, seed);
+
+    write_traces(&(options.output.to_owned() + ".trace"), &traces, TraceFormat::Daredevil);
+    write_inputs(&(options.output.to_owned() + ".input"), &inputs, ByteOrder::Reversed);
+
+    println!("Generated {} traces of length {} (seed {}).", traces.len(), traces[0].len(), seed);
+}
+
+fn chosen(options: ChosenArgs) {
+    let byte_order: ByteOrder = match options.byte_order.unwrap_or("reversed".to_owned()).parse() {
+        Ok(byte_order) => byte_order,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let groups = options.groups.unwrap_or(5);
+    let seed = options.seed.unwrap_or(time::precise_time_ns());
+
+    let inputs = chosen_inputs(groups, seed);
+    write_inputs(&(options.output.to_owned() + ".input"), &inputs, byte_order);
+
+    println!("Generated {} inputs in {} groups for each input byte (seed {}).", inputs.len(),
+             groups, seed);
+}
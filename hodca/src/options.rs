@@ -1,17 +1,201 @@
 use structopt::StructOpt;
+use std::cmp;
 use std::fmt;
+use std::str::FromStr;
+use readers::read_table;
 
 #[derive(Copy,Clone)]
 pub enum GuessType {
     Sbox,
     Inverse,
+    DesFirst,
+    DesLast,
+    Sm4,
+    Sm4Linear,
+    Present,
+    Gift,
+    Skinny,
+    Table([u8;256]),
+    TableOutput([u8;256]),
+    MixColumns([Option<u8>;16]),
+    InverseSbox,
+    InverseMul(u8),
+    TTable(Option<usize>),
+}
+
+impl GuessType {
+    /// The number of independently attacked key chunks
+    pub fn num_chunks(&self) -> usize {
+        match self {
+            GuessType::Sbox | GuessType::Inverse      => 16,
+            GuessType::DesFirst | GuessType::DesLast => 8,
+            GuessType::Sm4 | GuessType::Sm4Linear    => 4,
+            GuessType::Present | GuessType::Gift     => 16,
+            GuessType::Skinny                        => 8,
+            GuessType::Table(_) | GuessType::TableOutput(_) => 16,
+            GuessType::MixColumns(_)                 => 16,
+            GuessType::InverseSbox | GuessType::InverseMul(_) => 16,
+            GuessType::TTable(_)                     => 16,
+        }
+    }
+
+    /// The number of key bits in each chunk
+    pub fn chunk_bits(&self) -> usize {
+        match self {
+            GuessType::Sbox | GuessType::Inverse      => 8,
+            GuessType::DesFirst | GuessType::DesLast => 6,
+            GuessType::Sm4 | GuessType::Sm4Linear    => 8,
+            GuessType::Present | GuessType::Skinny   => 4,
+            GuessType::Gift                          => 2,
+            GuessType::Table(_) | GuessType::TableOutput(_) => 8,
+            GuessType::MixColumns(_)                 => 8,
+            GuessType::InverseSbox | GuessType::InverseMul(_) => 8,
+            GuessType::TTable(_)                     => 8,
+        }
+    }
+
+    /// The number of bits of the predicted value
+    pub fn output_bits(&self) -> usize {
+        match self {
+            GuessType::Sbox | GuessType::Inverse      => 8,
+            GuessType::DesFirst | GuessType::DesLast => 4,
+            GuessType::Sm4                           => 8,
+            GuessType::Sm4Linear                     => 32,
+            GuessType::Present | GuessType::Gift
+            | GuessType::Skinny                      => 4,
+            GuessType::Table(_) | GuessType::TableOutput(_) => 8,
+            GuessType::MixColumns(_)                 => 8,
+            GuessType::InverseSbox | GuessType::InverseMul(_) => 8,
+            GuessType::TTable(_)                     => 32,
+        }
+    }
+
+    /// The number of bits needed to represent the values predicted under a leakage model
+    pub fn predicted_bits(&self, model: LeakageModel) -> usize {
+        let max_value = match model {
+            LeakageModel::Value           => (1u64 << self.output_bits()) - 1,
+            LeakageModel::HammingWeight   => self.output_bits() as u64,
+            LeakageModel::HammingDistance => cmp::max(self.output_bits(), self.chunk_bits()) as u64,
+        };
+
+        (0..64).filter(|b| max_value >> b != 0).count()
+    }
+
+    /// The number of bytes of each input
+    pub fn input_size(&self) -> usize {
+        match self {
+            GuessType::Sbox | GuessType::Inverse      => 16,
+            GuessType::DesFirst | GuessType::DesLast => 8,
+            GuessType::Sm4 | GuessType::Sm4Linear    => 16,
+            GuessType::Present | GuessType::Gift
+            | GuessType::Skinny                      => 8,
+            GuessType::Table(_) | GuessType::TableOutput(_) => 16,
+            GuessType::MixColumns(_)                 => 16,
+            GuessType::InverseSbox | GuessType::InverseMul(_) => 16,
+            GuessType::TTable(_)                     => 16,
+        }
+    }
+
+    /// Whether the inputs are converted into cipher blocks with the byte order, rather than each
+    /// key chunk being added to the input byte with the same index
+    pub fn uses_block(&self) -> bool {
+        matches!(self, GuessType::DesFirst | GuessType::DesLast
+                       | GuessType::Sm4 | GuessType::Sm4Linear
+                       | GuessType::Present | GuessType::Gift | GuessType::Skinny
+                       | GuessType::MixColumns(_)
+                       | GuessType::InverseSbox | GuessType::InverseMul(_)
+                       | GuessType::TTable(_))
+    }
 }
 
 impl fmt::Debug for GuessType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            GuessType::Sbox    => write!(f, "S-box"),
-            GuessType::Inverse => write!(f, "Inverse"),
+            GuessType::Sbox      => write!(f, "S-box"),
+            GuessType::Inverse   => write!(f, "Inverse"),
+            GuessType::DesFirst  => write!(f, "DES round 1 S-box"),
+            GuessType::DesLast   => write!(f, "DES round 16 S-box"),
+            GuessType::Sm4       => write!(f, "SM4 S-box"),
+            GuessType::Sm4Linear => write!(f, "SM4 linear transform"),
+            GuessType::Present   => write!(f, "PRESENT round 1 S-box"),
+            GuessType::Gift      => write!(f, "GIFT-64 round 2 S-box"),
+            GuessType::Skinny    => write!(f, "SKINNY-64 round 2 S-box"),
+            GuessType::Table(_)       => write!(f, "Custom table, table[input ^ key]"),
+            GuessType::TableOutput(_) => write!(f, "Custom table, table[input] ^ key"),
+            GuessType::MixColumns(known) => {
+                write!(f, "MixColumns output, {} known key bytes",
+                       known.iter().filter(|k| k.is_some()).count())
+            },
+            GuessType::InverseSbox => write!(f, "Decryption inverse S-box"),
+            GuessType::InverseMul(c) => write!(f, "Decryption inverse S-box times {:02x}", c),
+            GuessType::TTable(Some(t)) => write!(f, "T-table Te{} output", t),
+            GuessType::TTable(None)    => write!(f, "T-table output, Te0..Te3 by row"),
+        }
+    }
+}
+
+impl FromStr for GuessType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sbox"    => Ok(GuessType::Sbox),
+            "inverse" => Ok(GuessType::Inverse),
+            "des1"    => Ok(GuessType::DesFirst),
+            "des16"   => Ok(GuessType::DesLast),
+            "sm4"     => Ok(GuessType::Sm4),
+            "sm4l"    => Ok(GuessType::Sm4Linear),
+            "present" => Ok(GuessType::Present),
+            "gift"    => Ok(GuessType::Gift),
+            "skinny"  => Ok(GuessType::Skinny),
+            _ if s.starts_with("table:")     => Ok(GuessType::Table(read_table(&s[6..])?)),
+            _ if s.starts_with("table-out:") => Ok(GuessType::TableOutput(read_table(&s[10..])?)),
+            "mc"      => Ok(GuessType::MixColumns([None;16])),
+            "invsbox" => Ok(GuessType::InverseSbox),
+            "invmul9"  => Ok(GuessType::InverseMul(0x09)),
+            "invmul11" => Ok(GuessType::InverseMul(0x0b)),
+            "invmul13" => Ok(GuessType::InverseMul(0x0d)),
+            "invmul14" => Ok(GuessType::InverseMul(0x0e)),
+            "ttable"  => Ok(GuessType::TTable(None)),
+            "te0"     => Ok(GuessType::TTable(Some(0))),
+            "te1"     => Ok(GuessType::TTable(Some(1))),
+            "te2"     => Ok(GuessType::TTable(Some(2))),
+            "te3"     => Ok(GuessType::TTable(Some(3))),
+            _ if s.starts_with("mc:") => match parse_partial_key(&s[3..]) {
+                Some(known) => Ok(GuessType::MixColumns(known)),
+                None => Err(format!("{:?} is not a valid partial key.", &s[3..])),
+            },
+            _ => Err(format!("{:?} is not a valid guess type.", s)),
+        }
+    }
+}
+
+#[derive(Copy,Clone)]
+pub enum LeakageModel {
+    Value,
+    HammingWeight,
+    HammingDistance,
+}
+
+impl fmt::Debug for LeakageModel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LeakageModel::Value           => write!(f, "Value"),
+            LeakageModel::HammingWeight   => write!(f, "Hamming weight"),
+            LeakageModel::HammingDistance => write!(f, "Hamming distance to input"),
+        }
+    }
+}
+
+impl FromStr for LeakageModel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "value" => Ok(LeakageModel::Value),
+            "hw"    => Ok(LeakageModel::HammingWeight),
+            "hd"    => Ok(LeakageModel::HammingDistance),
+            _ => Err(format!("{:?} is not a valid leakage model.", s)),
         }
     }
 }
@@ -21,14 +205,57 @@ pub enum CorrelationType {
     Pearson,
     Equality,
     Likelihood,
+    MutualInformation,
+    DifferenceOfMeans,
+    LinearDecoding,
+    ChiSquared,
 }
 
 impl fmt::Debug for CorrelationType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            CorrelationType::Pearson    => write!(f, "Pearson"),
-            CorrelationType::Equality   => write!(f, "Equality"),
-            CorrelationType::Likelihood => write!(f, "Likelihood"),
+            CorrelationType::Pearson           => write!(f, "Pearson"),
+            CorrelationType::Equality          => write!(f, "Equality"),
+            CorrelationType::Likelihood        => write!(f, "Likelihood"),
+            CorrelationType::MutualInformation => write!(f, "Mutual information"),
+            CorrelationType::DifferenceOfMeans => write!(f, "Difference of means"),
+            CorrelationType::LinearDecoding    => write!(f, "Linear decoding"),
+            CorrelationType::ChiSquared        => write!(f, "Chi-squared"),
+        }
+    }
+}
+
+impl CorrelationType {
+    /// Checks that linear decoding attacks bits, and that there are enough traces for the span of
+    /// a window not to contain every guess
+    pub fn validate(&self, data_type: DataType, window: usize, num_traces: usize)
+                    -> Result<(), String> {
+        match (self, data_type) {
+            (CorrelationType::LinearDecoding, DataType::Bytes) => {
+                Err("Linear decoding requires the bits data type.".to_owned())
+            },
+            (CorrelationType::LinearDecoding, _) if num_traces < 2*(window + 1) => {
+                Err(format!("Linear decoding with a window of {} needs at least {} traces.",
+                            window, 2*(window + 1)))
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+impl FromStr for CorrelationType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pearson"    => Ok(CorrelationType::Pearson),
+            "equality"   => Ok(CorrelationType::Equality),
+            "likelihood" => Ok(CorrelationType::Likelihood),
+            "mi"         => Ok(CorrelationType::MutualInformation),
+            "dom"        => Ok(CorrelationType::DifferenceOfMeans),
+            "lda"        => Ok(CorrelationType::LinearDecoding),
+            "chi2"       => Ok(CorrelationType::ChiSquared),
+            _ => Err(format!("{:?} is not a valid correlation type.", s)),
         }
     }
 }
@@ -48,9 +275,375 @@ impl fmt::Debug for DataType {
     }
 }
 
+impl FromStr for DataType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bits"  => Ok(DataType::Bits),
+            "bytes" => Ok(DataType::Bytes),
+            _ => Err(format!("{:?} is not a valid data type.", s)),
+        }
+    }
+}
+
+/// How the bytes of an input in the `.input` file are numbered
+#[derive(Copy,Clone)]
+pub enum ByteOrder {
+    Reversed,
+    AsIs,
+    ColumnMajor,
+}
+
+impl ByteOrder {
+    /// The offset in the file of input byte `index`, for inputs of `input_size` bytes
+    pub fn file_index(&self, index: usize, input_size: usize) -> usize {
+        match self {
+            ByteOrder::Reversed    => input_size - 1 - index,
+            ByteOrder::AsIs        => index,
+            ByteOrder::ColumnMajor => 4*(index % 4) + index / 4,
+        }
+    }
+
+    /// The input byte that is byte `index` of the cipher block, for inputs of `input_size` bytes.
+    /// The block is stored first byte first in the file, except for a column-major state, whose
+    /// input bytes are already numbered as the AES state
+    pub fn block_index(&self, index: usize, input_size: usize) -> usize {
+        match self {
+            ByteOrder::Reversed    => input_size - 1 - index,
+            ByteOrder::AsIs        => index,
+            ByteOrder::ColumnMajor => index,
+        }
+    }
+
+    /// Converts an input as returned by `read_inputs` into the bytes of the cipher block
+    pub fn block(&self, input: &[u8]) -> Vec<u8> {
+        (0..input.len()).map(|i| input[self.block_index(i, input.len())]).collect()
+    }
+
+    /// Converts the bytes of a cipher block into an input as returned by `read_inputs`, the
+    /// inverse of `block`
+    pub fn input(&self, block: &[u8]) -> Vec<u8> {
+        let mut input = vec![0;block.len()];
+
+        for (i, &x) in block.iter().enumerate() {
+            input[self.block_index(i, block.len())] = x;
+        }
+
+        input
+    }
+}
+
+impl fmt::Debug for ByteOrder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ByteOrder::Reversed    => write!(f, "Reversed"),
+            ByteOrder::AsIs        => write!(f, "As is"),
+            ByteOrder::ColumnMajor => write!(f, "Column-major state"),
+        }
+    }
+}
+
+impl FromStr for ByteOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "reversed"     => Ok(ByteOrder::Reversed),
+            "as-is"        => Ok(ByteOrder::AsIs),
+            "column-major" => Ok(ByteOrder::ColumnMajor),
+            _ => Err(format!("{:?} is not a valid byte order.", s)),
+        }
+    }
+}
+
+#[derive(Copy,Clone)]
+pub enum BitSelection {
+    Single,
+    Linear,
+}
+
+impl fmt::Debug for BitSelection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BitSelection::Single => write!(f, "Single bits"),
+            BitSelection::Linear => write!(f, "Linear combinations"),
+        }
+    }
+}
+
+impl BitSelection {
+    /// Checks that linear combinations are only formed over at most 8 predicted bits
+    pub fn validate(&self, guess_type: GuessType, model: LeakageModel) -> Result<(), String> {
+        match self {
+            BitSelection::Linear if guess_type.predicted_bits(model) > 8 => {
+                Err(format!("The {:?} target predicts {} bits under the {:?} model, linear \
+                             combinations require at most 8.", guess_type,
+                            guess_type.predicted_bits(model), model))
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+impl FromStr for BitSelection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "single" => Ok(BitSelection::Single),
+            "linear" => Ok(BitSelection::Linear),
+            _ => Err(format!("{:?} is not a valid bit selection.", s)),
+        }
+    }
+}
+
+#[derive(Copy,Clone)]
+pub enum Aggregation {
+    Sum,
+    Max,
+    Product,
+    BestK(usize),
+    Rank,
+}
+
+impl fmt::Debug for Aggregation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Aggregation::Sum      => write!(f, "Sum"),
+            Aggregation::Max      => write!(f, "Maximum"),
+            Aggregation::Product  => write!(f, "Product of normalised probabilities"),
+            Aggregation::BestK(k) => write!(f, "Sum of best {} bits", k),
+            Aggregation::Rank     => write!(f, "Rank fusion"),
+        }
+    }
+}
+
+impl Aggregation {
+    /// Checks that the scores are aggregated, and that best-k does not select more scores than
+    /// the attack produces. These are the scores of the predicted bits when bits are attacked,
+    /// and those of the predicted bytes for wider targets otherwise
+    pub fn validate(&self, guess_type: GuessType, model: LeakageModel,
+                    correlation_type: CorrelationType, data_type: DataType,
+                    bit_selection: BitSelection) -> Result<(), String> {
+        let predicted_bits = guess_type.predicted_bits(model);
+        let attack_bits = matches!((data_type, correlation_type),
+                                   (DataType::Bits, _) | (_, CorrelationType::DifferenceOfMeans));
+        let (num_scores, unit) = if attack_bits {
+            (predicted_bits, "bits")
+        } else {
+            (predicted_bits.div_ceil(8), "bytes")
+        };
+
+        match (self, bit_selection) {
+            (Aggregation::Sum, _) => Ok(()),
+            (_, BitSelection::Linear) => {
+                Err("Linear combinations keep the best mask of each guess, their scores are not \
+                     aggregated.".to_owned())
+            },
+            (Aggregation::BestK(k), _) if *k > num_scores => {
+                Err(format!("The {:?} target is attacked over {} {} under the {:?} model, best-{} \
+                             selects more.", guess_type, num_scores, unit, model, k))
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+impl FromStr for Aggregation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sum"     => Ok(Aggregation::Sum),
+            "max"     => Ok(Aggregation::Max),
+            "product" => Ok(Aggregation::Product),
+            "rank"    => Ok(Aggregation::Rank),
+            _ if s.starts_with("best-") => {
+                match s["best-".len()..].parse() {
+                    Ok(k) if k >= 1 => Ok(Aggregation::BestK(k)),
+                    _ => Err(format!("{:?} does not select a positive number of bits.", s)),
+                }
+            },
+            _ => Err(format!("{:?} is not a valid aggregation.", s)),
+        }
+    }
+}
+
+#[derive(Copy,Clone)]
+pub enum LeakageMetric {
+    Snr,
+    MutualInformation,
+    Variance,
+}
+
+impl fmt::Debug for LeakageMetric {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LeakageMetric::Snr               => write!(f, "SNR"),
+            LeakageMetric::MutualInformation => write!(f, "Mutual information"),
+            LeakageMetric::Variance          => write!(f, "Variance"),
+        }
+    }
+}
+
+impl FromStr for LeakageMetric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "snr"      => Ok(LeakageMetric::Snr),
+            "mi"       => Ok(LeakageMetric::MutualInformation),
+            "variance" => Ok(LeakageMetric::Variance),
+            _ => Err(format!("{:?} is not a valid leakage metric.", s)),
+        }
+    }
+}
+
+#[derive(Copy,Clone)]
+pub enum TraceFormat {
+    Daredevil,
+    Packed,
+    Text,
+}
+
+impl fmt::Debug for TraceFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TraceFormat::Daredevil => write!(f, "Daredevil"),
+            TraceFormat::Packed    => write!(f, "Packed"),
+            TraceFormat::Text      => write!(f, "Text"),
+        }
+    }
+}
+
+impl FromStr for TraceFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "daredevil" => Ok(TraceFormat::Daredevil),
+            "packed"    => Ok(TraceFormat::Packed),
+            "text"      => Ok(TraceFormat::Text),
+            _ => Err(format!("{:?} is not a valid trace format.", s)),
+        }
+    }
+}
+
+/// Parses a 16 byte key given as 32 hexadecimal characters. The first byte of the string is key
+/// byte 0, matching the order in which `attack_all` reports the key.
+pub fn parse_key(key: &str) -> Option<[u8;16]> {
+    if key.len() != 32 || !key.is_ascii() {
+        return None;
+    }
+
+    let mut parsed = [0;16];
+
+    for i in 0..16 {
+        parsed[i] = u8::from_str_radix(&key[2*i..2*i+2], 16).ok()?;
+    }
+
+    Some(parsed)
+}
+
+/// Parses a partial 16 byte key in the format of `parse_key`, where unknown bytes are written as
+/// `..`
+pub fn parse_partial_key(key: &str) -> Option<[Option<u8>;16]> {
+    if key.len() != 32 || !key.is_ascii() {
+        return None;
+    }
+
+    let mut parsed = [None;16];
+
+    for i in 0..16 {
+        parsed[i] = match &key[2*i..2*i+2] {
+            ".." => None,
+            byte => Some(u8::from_str_radix(byte, 16).ok()?),
+        };
+    }
+
+    Some(parsed)
+}
+
 #[derive(StructOpt)]
 #[structopt(name = "Higher Order DCA", about = "Apply higher order DCA to traces.")]
-pub struct InputArgs {
+pub enum Command {
+    #[structopt(name = "attack")]
+    /**
+    Apply higher order DCA to a trace set.
+    */
+    Attack(AttackArgs),
+
+    #[structopt(name = "campaign")]
+    /**
+    Run a campaign of attacks described in a TOML or JSON file over the same trace set.
+    */
+    Campaign(CampaignArgs),
+
+    #[structopt(name = "roi")]
+    /**
+    Propose regions of interest for each key byte from first order leakage statistics.
+    */
+    Roi(RoiArgs),
+
+    #[structopt(name = "map")]
+    /**
+    Map samples or tuples to the input byte they depend on, without a key hypothesis.
+    */
+    Map(MapArgs),
+
+    #[structopt(name = "tvla")]
+    /**
+    Non-specific leakage assessment with Welch's t-test between two groups of traces.
+    */
+    Tvla(TvlaArgs),
+
+    #[structopt(name = "collide")]
+    /**
+    Recover differences between key bytes from collisions between their regions of interest.
+    */
+    Collide(CollideArgs),
+
+    #[structopt(name = "bge")]
+    /**
+    Recover the encodings and first round key of a Chow-style white-box from its encoded round
+    outputs, with a Billet-Gilbert-Ech-Chatbi style analysis of traces of chosen inputs.
+    */
+    Bge(BgeArgs),
+
+    #[structopt(name = "info")]
+    /**
+    Describe the contents of a trace set.
+    */
+    Info(InfoArgs),
+
+    #[structopt(name = "convert")]
+    /**
+    Convert a trace set between trace formats.
+    */
+    Convert(ConvertArgs),
+
+    #[structopt(name = "preprocess")]
+    /**
+    Filter a trace set and write the result to a new trace set.
+    */
+    Preprocess(PreprocessArgs),
+
+    #[structopt(name = "simulate")]
+    /**
+    Generate a synthetic trace set of a (masked) AES first round.
+    */
+    Simulate(SimulateArgs),
+
+    #[structopt(name = "chosen")]
+    /**
+    Write chosen inputs for bge, in which one input byte at a time takes all 256 values.
+    */
+    Chosen(ChosenArgs),
+}
+
+#[derive(StructOpt)]
+pub struct TraceArgs {
     #[structopt(long = "path")]
     /**
     Path to trace files. The files read are <path>.trace and <path>.input.
@@ -59,15 +652,41 @@ pub struct InputArgs {
 
     #[structopt(long = "traces")]
     /**
-    The number of traces in the input files.
+    The number of traces to use. Defaults to the number of inputs in <path>.input.
     */
-    pub traces: usize,
+    pub traces: Option<usize>,
 
     #[structopt(long = "length")]
     /**
-    The length of each trace in the input file.
+    The length of each trace in the input file. Defaults to the length implied by the size of
+    <path>.trace, or by the file name if it follows the Deadpool naming convention. Required if
+    neither <path>.input nor the file name gives the number of traces in <path>.trace.
+    */
+    pub length: Option<usize>,
+
+    #[structopt(long = "input_size")]
+    /**
+    The number of bytes of each input in <path>.input. Defaults to the block size of the target,
+    or 16 for commands without a target.
+    */
+    pub input_size: Option<usize>,
+
+    #[structopt(long = "byte_order")]
+    /**
+    How the bytes of each input are numbered. Valid inputs: reversed (input byte i is the i-th
+    byte from the end of the input in the file), as-is (input byte i is the i-th byte in the file),
+    column-major (the file holds an AES state row by row, and input byte i is row i % 4 of column
+    i / 4). All targets but sbox, inverse and the custom tables take the cipher block from the
+    file first byte first, or column by column for column-major, and number AES key bytes as the
+    state. Defaults to reversed.
     */
-    pub length: usize,
+    pub byte_order: Option<String>,
+}
+
+#[derive(StructOpt)]
+pub struct AttackArgs {
+    #[structopt(flatten)]
+    pub trace_args: TraceArgs,
 
     #[structopt(long = "order")]
     /**
@@ -87,6 +706,19 @@ pub struct InputArgs {
     */
     pub stop: Option<usize>,
 
+    #[structopt(long = "region", number_of_values = 1)]
+    /**
+    Region of interest of a single key byte, overriding start and stop for that byte. Given as
+    <byte>:<start>-<stop>,<start>-<stop>,... and can be repeated for several key bytes.
+    */
+    pub region: Vec<String>,
+
+    #[structopt(long = "region_file")]
+    /**
+    File with regions of interest, one key byte per line in the same format as --region.
+    */
+    pub region_file: Option<String>,
+
     #[structopt(long = "window")]
     /**
     Size of the window to use. 
@@ -101,7 +733,8 @@ pub struct InputArgs {
 
     #[structopt(long = "correlation")]
     /**
-    The type of correlation to use. Valid inputs: pearson, equality, likelihood.
+    The type of correlation to use. Valid inputs: pearson, equality, likelihood, mi, dom, lda,
+    chi2.
     */
     pub correlation: String,
 
@@ -113,7 +746,468 @@ pub struct InputArgs {
 
     #[structopt(long = "guess")]
     /**
-    Type of guess to use as target. Valid inputs: sbox, inverse.
+    Type of guess to use as target. Valid inputs: sbox, inverse, des1 (DES round 1 S-box outputs,
+    with the plaintext as input), des16 (DES round 16 S-box outputs, with the ciphertext as
+    input), sm4 (SM4 S-box outputs), sm4l (contribution of each SM4 S-box to the 32-bit output of
+    the linear transform), present (PRESENT round 1 S-box outputs), gift (GIFT-64 round 2 S-box
+    outputs, with 2 key bits per chunk), skinny (SKINNY-64 round 2 S-box outputs),
+    table:<file> (table[input ^ key] for a 256-entry table read from file), table-out:<file>
+    (table[input] ^ key), mc (the byte of the first MixColumns output in which the S-box output
+    of the key byte is doubled), mc:<key> (the same, adding the S-box outputs of the known bytes
+    of a partial key, written in hexadecimal first byte first with .. for unknown bytes), invsbox (inverse S-box
+    outputs of the first decryption round, with the ciphertext as input), invmul9, invmul11,
+    invmul13, invmul14 (the same multiplied by an InvMixColumns coefficient), te0, te1, te2, te3
+    (the 32-bit T-table entry Te0..Te3 of the S-box output), ttable (the T-table of the row of
+    each key byte, Te0 for bytes 0, 4, 8 and 12). Tables are binary files of 256 bytes, or
+    hexadecimal text. SM4 targets attack the round after those given by --round_keys.
     */
     pub guess: String,
-}
\ No newline at end of file
+
+    #[structopt(long = "model")]
+    /**
+    Leakage model applied to the target. Valid inputs: value (the target itself), hw (Hamming
+    weight of the target), hd (Hamming distance between the input byte and the target). Defaults
+    to value.
+    */
+    pub model: Option<String>,
+
+    #[structopt(long = "bits")]
+    /**
+    Which bits of the guess to attack. Valid inputs: single (each of the 8 bits), linear (the
+    parity of each of the 255 non-zero linear combinations of bits). Defaults to single.
+    */
+    pub bits: Option<String>,
+
+    #[structopt(long = "aggregation")]
+    /**
+    How the scores of the single bits are combined into a key score. Valid inputs: sum, max,
+    product (of the scores normalised to probabilities), best-<k> (sum of the best k bits, at most
+    the number of attacked bits, or bytes for targets wider than a byte), rank (mean rank over the
+    bits). Targets of at most 8 bits attacked as bytes produce a single score. Linear combinations
+    keep the best mask of each guess instead, and only accept sum. Defaults to sum.
+    */
+    pub aggregation: Option<String>,
+
+    #[structopt(long = "known_pair")]
+    /**
+    A known plaintext and ciphertext in hexadecimal, separated by a colon. Selects the key from
+    the candidates left after inverting the key schedule. Only used for DES, SM4 and AES (sbox,
+    T-table, invsbox, invmul) targets, for AES first byte first as in the reconstructed keys. DES
+    keys are checked with single DES encryption, so leave this out for triple DES, of which des1
+    and des16 recover the first and the last key.
+    */
+    pub known_pair: Option<String>,
+
+    #[structopt(long = "round_keys")]
+    /**
+    Previously recovered round keys in hexadecimal, separated by commas: up to three SM4 round
+    keys, or the first AES round key for sbox and the T-table targets. The inputs are encrypted
+    with these rounds, so the next round key is attacked. Once four SM4 round keys or two AES
+    round keys are known, the key is reconstructed. AES round keys are given first byte first, as
+    the round 1 key is printed.
+    */
+    pub round_keys: Option<String>,
+}
+
+#[derive(StructOpt)]
+pub struct CampaignArgs {
+    #[structopt(long = "config")]
+    /**
+    Path to the campaign description. Files ending in .json are read as JSON, all other files as
+    TOML.
+    */
+    pub config: String,
+}
+
+#[derive(StructOpt)]
+pub struct RoiArgs {
+    #[structopt(flatten)]
+    pub trace_args: TraceArgs,
+
+    #[structopt(long = "data_type")]
+    /**
+    The data type to use. Valid inputs: bits, bytes.
+    */
+    pub data_type: String,
+
+    #[structopt(long = "metric")]
+    /**
+    The statistic computed for each sample. Valid inputs: snr (against the input byte), mi
+    (mutual information with the input byte), variance.
+    */
+    pub metric: String,
+
+    #[structopt(long = "threshold")]
+    /**
+    Samples scoring at least this fraction of the highest score of a key byte are included in its
+    region. Defaults to 0.5.
+    */
+    pub threshold: Option<f64>,
+
+    #[structopt(long = "margin")]
+    /**
+    Number of samples added on each side of the selected samples. Defaults to 8.
+    */
+    pub margin: Option<usize>,
+
+    #[structopt(long = "output")]
+    /**
+    File to write the proposed regions to, in the format read by --region_file.
+    */
+    pub output: Option<String>,
+}
+
+#[derive(StructOpt)]
+pub struct MapArgs {
+    #[structopt(flatten)]
+    pub trace_args: TraceArgs,
+
+    #[structopt(long = "order")]
+    /**
+    The number of samples combined into a tuple. Defaults to one.
+    */
+    pub order: Option<usize>,
+
+    #[structopt(long = "start")]
+    /**
+    Position in each trace to start analysis. Defaults to zero.
+    */
+    pub start: Option<usize>,
+
+    #[structopt(long = "stop")]
+    /**
+    Position in each trace to stop analysis. Defaults to trace length.
+    */
+    pub stop: Option<usize>,
+
+    #[structopt(long = "window")]
+    /**
+    Size of the window to use for higher orders. Defaults to the order.
+    */
+    pub window: Option<usize>,
+
+    #[structopt(long = "data_type")]
+    /**
+    The data type to use. Valid inputs: bits, bytes.
+    */
+    pub data_type: String,
+
+    #[structopt(long = "threshold")]
+    /**
+    A sample or tuple is mapped to an input byte if its normalised mutual information with that
+    byte, minus the median over all input bytes, is at least this value for exactly one byte.
+    Defaults to 0.5.
+    */
+    pub threshold: Option<f64>,
+
+    #[structopt(long = "margin")]
+    /**
+    Number of samples added on each side of the mapped samples in the proposed regions. Defaults
+    to 8.
+    */
+    pub margin: Option<usize>,
+
+    #[structopt(long = "output_size")]
+    /**
+    The number of mapped samples or tuples to display.
+    */
+    pub output_size: Option<usize>,
+
+    #[structopt(long = "output")]
+    /**
+    File to write all mapped samples or tuples to, one per line followed by the input byte and
+    the score.
+    */
+    pub output: Option<String>,
+
+    #[structopt(long = "region_output")]
+    /**
+    File to write the proposed regions to, in the format read by --region_file.
+    */
+    pub region_output: Option<String>,
+}
+
+#[derive(StructOpt)]
+pub struct TvlaArgs {
+    #[structopt(flatten)]
+    pub trace_args: TraceArgs,
+
+    #[structopt(long = "other")]
+    /**
+    Path to a second trace set with the same trace length, used as the second group. The file
+    read is <other>.trace.
+    */
+    pub other: Option<String>,
+
+    #[structopt(long = "labels")]
+    /**
+    File with the group (0 or 1) of each trace of <path>, separated by whitespace. Used if no
+    second trace set is given.
+    */
+    pub labels: Option<String>,
+
+    #[structopt(long = "order")]
+    /**
+    The order of the test. Defaults to one.
+    */
+    pub order: Option<usize>,
+
+    #[structopt(long = "start")]
+    /**
+    Position in each trace to start analysis. Defaults to zero.
+    */
+    pub start: Option<usize>,
+
+    #[structopt(long = "stop")]
+    /**
+    Position in each trace to stop analysis. Defaults to trace length.
+    */
+    pub stop: Option<usize>,
+
+    #[structopt(long = "window")]
+    /**
+    Size of the window to use for higher orders. Defaults to the order.
+    */
+    pub window: Option<usize>,
+
+    #[structopt(long = "data_type")]
+    /**
+    The data type to use. Valid inputs: bits, bytes.
+    */
+    pub data_type: String,
+
+    #[structopt(long = "threshold")]
+    /**
+    Report samples or tuples whose t-statistic exceeds this value in absolute value. Defaults
+    to 4.5.
+    */
+    pub threshold: Option<f64>,
+
+    #[structopt(long = "output_size")]
+    /**
+    The number of leaking samples or tuples to display.
+    */
+    pub output_size: Option<usize>,
+
+    #[structopt(long = "output")]
+    /**
+    File to write all leaking samples or tuples to, one per line followed by the t-statistic.
+    */
+    pub output: Option<String>,
+}
+
+#[derive(StructOpt)]
+pub struct CollideArgs {
+    #[structopt(flatten)]
+    pub trace_args: TraceArgs,
+
+    #[structopt(long = "region", number_of_values = 1)]
+    /**
+    Region of interest of a single key byte, given as <byte>:<start>-<stop>,<start>-<stop>,...
+    and repeated for several key bytes. All regions must contain the same number of samples.
+    */
+    pub region: Vec<String>,
+
+    #[structopt(long = "region_file")]
+    /**
+    File with regions of interest, one key byte per line in the same format as --region.
+    */
+    pub region_file: Option<String>,
+
+    #[structopt(long = "data_type")]
+    /**
+    The data type to use. Valid inputs: bits, bytes.
+    */
+    pub data_type: String,
+
+    #[structopt(long = "output")]
+    /**
+    File to write the key candidates to, one per line. Only written if there are at most 65536
+    candidates.
+    */
+    pub output: Option<String>,
+}
+
+#[derive(StructOpt)]
+pub struct BgeArgs {
+    #[structopt(flatten)]
+    pub trace_args: TraceArgs,
+
+    #[structopt(long = "start")]
+    /**
+    Position in each trace to start analysis. Defaults to zero.
+    */
+    pub start: Option<usize>,
+
+    #[structopt(long = "stop")]
+    /**
+    Position in each trace to stop analysis. Defaults to trace length.
+    */
+    pub stop: Option<usize>,
+
+    #[structopt(long = "known_pair")]
+    /**
+    A known plaintext and ciphertext in hexadecimal, separated by a colon, in the byte order of
+    the printed key. Used to verify the recovered key.
+    */
+    pub known_pair: Option<String>,
+
+    #[structopt(long = "output_size")]
+    /**
+    The number of encoded samples to display.
+    */
+    pub output_size: Option<usize>,
+
+    #[structopt(long = "output")]
+    /**
+    File to write the recovered encodings to, one sample per line followed by the MixColumns
+    output byte it holds and the 256 encoded values in hexadecimal.
+    */
+    pub output: Option<String>,
+}
+
+#[derive(StructOpt)]
+pub struct InfoArgs {
+    #[structopt(flatten)]
+    pub trace_args: TraceArgs,
+
+    #[structopt(long = "data_type")]
+    /**
+    The data type to use. Valid inputs: bits, bytes.
+    */
+    pub data_type: String,
+
+    #[structopt(long = "output")]
+    /**
+    File to write the entropy of each sample to, one sample per line.
+    */
+    pub output: Option<String>,
+}
+
+#[derive(StructOpt)]
+pub struct ConvertArgs {
+    #[structopt(flatten)]
+    pub trace_args: TraceArgs,
+
+    #[structopt(long = "from")]
+    /**
+    Format of the input trace file. Valid inputs: daredevil, packed, text.
+    */
+    pub from: String,
+
+    #[structopt(long = "to")]
+    /**
+    Format of the output trace file. Valid inputs: daredevil, packed, text.
+    */
+    pub to: String,
+
+    #[structopt(long = "output")]
+    /**
+    Path to output files. The files written are <output>.trace and <output>.input.
+    */
+    pub output: String,
+}
+
+#[derive(StructOpt)]
+pub struct PreprocessArgs {
+    #[structopt(flatten)]
+    pub trace_args: TraceArgs,
+
+    #[structopt(long = "output")]
+    /**
+    Path to output files. The files written are <output>.trace and <output>.input.
+    */
+    pub output: String,
+
+    #[structopt(long = "start")]
+    /**
+    Position in each trace to start the new traces. Defaults to zero.
+    */
+    pub start: Option<usize>,
+
+    #[structopt(long = "stop")]
+    /**
+    Position in each trace to stop the new traces. Defaults to trace length.
+    */
+    pub stop: Option<usize>,
+
+    #[structopt(long = "remove_constant")]
+    /**
+    Remove samples that have the same value in all traces.
+    */
+    pub remove_constant: bool,
+
+    #[structopt(long = "deduplicate")]
+    /**
+    Remove samples that are identical to an earlier sample in all traces.
+    */
+    pub deduplicate: bool,
+}
+
+#[derive(StructOpt)]
+pub struct SimulateArgs {
+    #[structopt(long = "output")]
+    /**
+    Path to output files. The files written are <output>.trace and <output>.input.
+    */
+    pub output: String,
+
+    #[structopt(long = "traces")]
+    /**
+    The number of traces to generate, at least one.
+    */
+    pub traces: usize,
+
+    #[structopt(long = "key")]
+    /**
+    The key used to generate the traces, as 32 hexadecimal characters.
+    */
+    pub key: String,
+
+    #[structopt(long = "masking_order")]
+    /**
+    The number of random masks applied to each S-box output. Defaults to zero.
+    */
+    pub masking_order: Option<usize>,
+
+    #[structopt(long = "noise")]
+    /**
+    The number of random bytes written before each S-box output. Defaults to zero.
+    */
+    pub noise: Option<usize>,
+
+    #[structopt(long = "seed")]
+    /**
+    Seed of the random number generator. Defaults to the current time.
+    */
+    pub seed: Option<u64>,
+}
+
+#[derive(StructOpt)]
+pub struct ChosenArgs {
+    #[structopt(long = "output")]
+    /**
+    Path to the output file. The file written is <output>.input.
+    */
+    pub output: String,
+
+    #[structopt(long = "groups")]
+    /**
+    The number of groups of 256 inputs for each input byte, whose other bytes are random. Each
+    group adds a translation of the encodings, so at least three are needed, and more make it
+    likely that the translations span all of them. Defaults to 5.
+    */
+    pub groups: Option<usize>,
+
+    #[structopt(long = "byte_order")]
+    /**
+    How the bytes of each input are numbered, as for the trace commands. Defaults to reversed.
+    */
+    pub byte_order: Option<String>,
+
+    #[structopt(long = "seed")]
+    /**
+    Seed of the random number generator. Defaults to the current time.
+    */
+    pub seed: Option<u64>,
+}
@@ -0,0 +1,46 @@
+use std::collections::HashSet;
+use readers::Trace;
+
+/// Extracts the values of a single sample across all traces
+fn sample_column(traces: &[Trace], sample: usize) -> Vec<u8> {
+    traces.iter().map(|t| t[sample]).collect()
+}
+
+/// Finds the samples within `bounds` to keep. Constant samples are samples with the same value in
+/// all traces, duplicate samples are samples that are equal to an earlier kept sample in all
+/// traces.
+pub fn select_samples(bounds: (usize,usize),
+                      remove_constant: bool,
+                      deduplicate: bool,
+                      traces: &[Trace])
+                      -> Vec<usize> {
+    if bounds.0 > bounds.1 || bounds.1 > traces[0].len() {
+        panic!("[ERROR] select_samples: start or stop position out of bounds.");
+    }
+
+    let mut seen = HashSet::new();
+    let mut samples = vec![];
+
+    for i in bounds.0..bounds.1 {
+        let column = sample_column(traces, i);
+
+        if remove_constant && column.iter().all(|&x| x == column[0]) {
+            continue;
+        }
+
+        if deduplicate && !seen.insert(column) {
+            continue;
+        }
+
+        samples.push(i);
+    }
+
+    samples
+}
+
+/// Creates a new set of traces containing only the given samples
+pub fn filter_traces(samples: &[usize], traces: &[Trace]) -> Vec<Trace> {
+    traces.iter()
+          .map(|t| samples.iter().map(|&i| t[i]).collect())
+          .collect()
+}
@@ -1,6 +1,6 @@
 use std::fs::{self, File};
-use std::io::{self, Read, Write};
-use options::DataType;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use options::{ByteOrder, DataType, TraceFormat};
 
 /// A struct representing a progress bar for progress printing on the command line.
 struct ProgressBar {
@@ -48,6 +48,146 @@ impl Drop for ProgressBar {
 /// A trace is a vector of bytes
 pub type Trace = Vec<u8>;
 
+/// Default number of bytes of each input in the input file, the block size of AES
+pub const INPUT_SIZE: usize = 16;
+
+/// Parses the number of traces and trace length from a file name following the Deadpool naming
+/// convention, e.g. `data_W_100_8096` for 100 traces of length 8096.
+pub fn parse_trace_name(path: &str) -> Option<(usize,usize)> {
+    let name = path.rsplit('/').next()?;
+    let mut parts = name.rsplit('_');
+    let length = parts.next()?.parse().ok()?;
+    let num_traces = parts.next()?.parse().ok()?;
+
+    Some((num_traces, length))
+}
+
+/// Determines the number of traces and the trace length of the trace set at `path`. Missing
+/// values are inferred from the sizes of `<path>.trace` and `<path>.input`, falling back to the
+/// Deadpool naming convention, with inputs of `input_size` bytes. Returns an error if the values
+/// are inconsistent with the files or the file name, or if the length cannot be determined
+/// because the number of traces stored in the trace file is unknown.
+pub fn trace_dimensions(path: &str,
+                        num_traces: Option<usize>,
+                        length: Option<usize>,
+                        format: TraceFormat,
+                        input_size: usize)
+                        -> Result<(usize,usize), String> {
+    let trace_path = path.to_owned() + ".trace";
+    let input_path = path.to_owned() + ".input";
+    let name_dimensions = parse_trace_name(path);
+
+    let trace_size = fs::metadata(&trace_path)
+                        .map_err(|e| format!("Could not read {}: {}", trace_path, e))?
+                        .len();
+
+    let available_inputs = match fs::metadata(&input_path) {
+        Ok(metadata) => {
+            if metadata.len() % (input_size as u64) != 0 {
+                return Err(format!("The size of {} is not a multiple of {} bytes.",
+                                   input_path, input_size));
+            }
+
+            Some((metadata.len() / (input_size as u64)) as usize)
+        },
+        Err(_) => None,
+    };
+
+    // The number of traces stored in the files, which may be more than the number requested
+    let stored_traces = available_inputs.or(name_dimensions.map(|x| x.0));
+    let num_traces = num_traces.or(stored_traces)
+                               .ok_or("Could not determine the number of traces.")?;
+
+    if num_traces == 0 {
+        return Err("The number of traces must be positive.".to_owned());
+    }
+
+    if let Some(available_inputs) = available_inputs {
+        if num_traces > available_inputs {
+            return Err(format!("Requested {} traces, but {} only contains {} inputs.",
+                               num_traces, input_path, available_inputs));
+        }
+    }
+
+    // Without an input file or a file name giving it, the number of stored traces is unknown and
+    // the length cannot be derived from the size of the trace file
+    let unknown_length = || format!("The number of traces in {} is unknown, so --length is \
+                                     required.", trace_path);
+
+    let length = match (length, format) {
+        (Some(length), _) => length,
+        (None, TraceFormat::Daredevil) => {
+            let stored_traces = stored_traces.ok_or_else(unknown_length)?;
+
+            if trace_size % (stored_traces as u64) != 0 {
+                return Err(format!("The size of {} is not a multiple of {} traces.",
+                                   trace_path, stored_traces));
+            }
+
+            (trace_size / (stored_traces as u64)) as usize
+        },
+        (None, TraceFormat::Packed) => {
+            match name_dimensions {
+                Some((_, length)) => length,
+                None => 8 * (trace_size / (stored_traces.ok_or_else(unknown_length)? as u64))
+                            as usize,
+            }
+        },
+        (None, TraceFormat::Text) => {
+            let file = File::open(&trace_path)
+                           .map_err(|e| format!("Could not read {}: {}", trace_path, e))?;
+            let line = BufReader::new(file).lines().next()
+                                           .ok_or(format!("{} is empty.", trace_path))?
+                                           .map_err(|e| format!("Could not read {}: {}",
+                                                                trace_path, e))?;
+
+            line.split_whitespace().count()
+        },
+    };
+
+    if length == 0 {
+        return Err("The trace length must be positive.".to_owned());
+    }
+
+    // Check that the trace file holds a whole number of traces of the given length
+    let trace_bytes = match format {
+        TraceFormat::Daredevil => Some(length as u64),
+        TraceFormat::Packed    => Some(length.div_ceil(8) as u64),
+        TraceFormat::Text      => None,
+    };
+
+    if let Some(trace_bytes) = trace_bytes {
+        if trace_size % trace_bytes != 0 {
+            return Err(format!("The size of {} ({} bytes) is not a multiple of the trace length {}.",
+                               trace_path, trace_size, length));
+        }
+
+        let contained = (trace_size / trace_bytes) as usize;
+
+        if let Some(available_inputs) = available_inputs {
+            if contained != available_inputs {
+                return Err(format!("{} contains {} traces of length {}, but {} contains {} inputs.",
+                                   trace_path, contained, length, input_path, available_inputs));
+            }
+        }
+
+        if num_traces > contained {
+            return Err(format!("Requested {} traces, but {} only contains {} traces of length {}.",
+                               num_traces, trace_path, contained, length));
+        }
+
+        if let Some(name_dimensions) = name_dimensions {
+            if name_dimensions != (contained, length) {
+                return Err(format!("The file name suggests {} traces of length {}, but {} \
+                                    contains {} traces of length {}.", name_dimensions.0,
+                                   name_dimensions.1, trace_path, contained, length));
+            }
+        }
+    }
+
+    Ok((num_traces, length))
+}
+
 /// Reads a DCA trace from file.
 pub fn read_traces(trace_path: &str, 
                    num_traces: usize, 
@@ -112,33 +252,250 @@ pub fn read_traces(trace_path: &str,
     traces
 }
 
-/// Reads inputs associated with a DCA trace from file.
-pub fn read_inputs(input_path: &str, num_inputs: usize) -> Vec<Vec<u8>> {
+/// Packs bit-serialized traces into bytes, giving the same traces as reading with
+/// `DataType::Bytes`.
+pub fn pack_traces(traces: &[Trace]) -> Vec<Trace> {
+    traces.iter().map(|trace| {
+        let mut packed = vec![0;trace.len()/8];
+
+        for (i, byte) in packed.iter_mut().enumerate() {
+            for b in 0..8 {
+                *byte ^= trace[8*i + b] << b;
+            }
+        }
+
+        packed
+    }).collect()
+}
+
+/// Reads a trace set stored in the given format. The traces are always returned bit-serialized,
+/// i.e. as if read with `DataType::Bits`.
+pub fn read_trace_set(trace_path: &str, 
+                      num_traces: usize, 
+                      length: usize, 
+                      format: TraceFormat) 
+                      -> Vec<Trace> {
+    match format {
+        TraceFormat::Daredevil => read_traces(trace_path, num_traces, length, DataType::Bits),
+        TraceFormat::Packed    => read_packed_traces(trace_path, num_traces, length),
+        TraceFormat::Text      => read_text_traces(trace_path, num_traces, length),
+    }
+}
+
+/// Reads a trace file where each byte holds eight consecutive samples, least significant bit
+/// first. Each trace occupies `ceil(length/8)` bytes.
+fn read_packed_traces(trace_path: &str, num_traces: usize, length: usize) -> Vec<Trace> {
+    let mut file = File::open(trace_path).expect("Could not open file.");
+    let metadata = fs::metadata(trace_path).expect("Could not get metadata.");
+    let packed_length = length.div_ceil(8);
+
+    if metadata.len() < (num_traces*packed_length) as u64 {
+        panic!("[ERROR] read_packed_traces: trace file is not the correct size.");
+    }
+
+    let mut traces = vec![vec![0;length];num_traces];
+    let mut packed = vec![0;packed_length];
+    let mut progress_bar = ProgressBar::new(num_traces);
+
+    println!("Reading trace file...");
+
+    for trace in traces.iter_mut() {
+        file.read_exact(&mut packed).expect("Could not read trace.");
+
+        for (i, sample) in trace.iter_mut().enumerate() {
+            *sample = (packed[i/8] >> (i%8)) & 0x1;
+        }
+
+        progress_bar.increment();
+    }
+
+    traces
+}
+
+/// Reads a text trace file with one trace per line, where samples are separated by whitespace.
+fn read_text_traces(trace_path: &str, num_traces: usize, length: usize) -> Vec<Trace> {
+    let file = File::open(trace_path).expect("Could not open file.");
+    let mut traces = Vec::with_capacity(num_traces);
+
+    println!("Reading trace file...");
+
+    for line in BufReader::new(file).lines().take(num_traces) {
+        let line = line.expect("Could not read line.");
+        let trace: Trace = line.split_whitespace()
+                               .map(|x| x.parse().expect("Could not parse sample."))
+                               .collect();
+
+        if trace.len() < length {
+            panic!("[ERROR] read_text_traces: trace {} is too short.", traces.len());
+        }
+
+        traces.push(trace[0..length].to_vec());
+    }
+
+    if traces.len() < num_traces {
+        panic!("[ERROR] read_text_traces: trace file is not the correct size.");
+    }
+
+    traces
+}
+
+/// Reads inputs of `input_size` bytes associated with a DCA trace from file. The bytes of each
+/// input are numbered according to `byte_order`.
+pub fn read_inputs(input_path: &str,
+                   num_inputs: usize,
+                   input_size: usize,
+                   byte_order: ByteOrder)
+                   -> Vec<Vec<u8>> {
     let file = File::open(input_path).expect("Could not open file.");
     let metadata = fs::metadata(input_path).expect("Could not get metadata.");
 
-    if metadata.len() < (num_inputs as u64)*16 {
+    if metadata.len() < (num_inputs as u64)*(input_size as u64) {
         panic!("[ERROR] read_inputs: input file is not the correct size.");
     }
 
+    if let (ByteOrder::ColumnMajor, false) = (byte_order, input_size == 16) {
+        panic!("[ERROR] read_inputs: a column-major state requires 16 byte inputs.");
+    }
+
     let (mut current_input, mut current_byte) = (0,0);
-    let mut inputs = vec![vec![0;16];num_inputs];
+    let mut block = vec![0;input_size];
+    let mut inputs = vec![vec![0;input_size];num_inputs];
 
     for byte in file.bytes() {
         if current_input == num_inputs {
             break;
         }
 
-        // First byte read is the MSB
-        inputs[current_input][15-current_byte] = byte.expect("Could not get byte.");
-
+        block[current_byte] = byte.expect("Could not get byte.");
         current_byte += 1;
 
-        if current_byte == 16 {
+        if current_byte == input_size {
+            for (i, x) in inputs[current_input].iter_mut().enumerate() {
+                *x = block[byte_order.file_index(i, input_size)];
+            }
+
             current_byte = 0;
             current_input += 1;
         }
     }
 
     inputs
-}
\ No newline at end of file
+}
+
+/// Reads a table of 256 bytes, such as a custom S-box, from file. Files of exactly 256 bytes are
+/// read as binary, other files as hexadecimal text: entries separated by whitespace or commas,
+/// with an optional `0x` prefix, or a single string of 512 hexadecimal characters. Lines starting
+/// with `#` are ignored.
+pub fn read_table(table_path: &str) -> Result<[u8;256], String> {
+    let contents = fs::read(table_path)
+                      .map_err(|e| format!("Could not read {}: {}", table_path, e))?;
+    let mut table = [0u8;256];
+
+    if contents.len() == 256 {
+        table.copy_from_slice(&contents);
+        return Ok(table);
+    }
+
+    let text = String::from_utf8(contents)
+                      .map_err(|_| format!("{} is neither a binary nor a text table.", table_path))?;
+    let mut entries: Vec<String> = text.lines()
+                                       .filter(|l| !l.trim().starts_with('#'))
+                                       .flat_map(|l| l.split(|c: char| c.is_whitespace() || c == ','))
+                                       .filter(|x| !x.is_empty())
+                                       .map(|x| x.trim_start_matches("0x").to_owned())
+                                       .collect();
+
+    if entries.len() == 1 && entries[0].len() == 512 && entries[0].is_ascii() {
+        entries = (0..256).map(|i| entries[0][2*i..2*i+2].to_owned()).collect();
+    }
+
+    if entries.len() != 256 {
+        return Err(format!("{} contains {} entries instead of 256.", table_path, entries.len()));
+    }
+
+    for (entry, x) in table.iter_mut().zip(&entries) {
+        *entry = u8::from_str_radix(x, 16)
+                    .map_err(|_| format!("{:?} is not a valid table entry.", x))?;
+    }
+
+    Ok(table)
+}
+
+/// Reads the group labels of traces from a text file, one label (0 or 1) per trace separated by
+/// whitespace.
+pub fn read_labels(label_path: &str, num_labels: usize) -> Vec<u8> {
+    let contents = fs::read_to_string(label_path).expect("Could not read file.");
+    let labels: Vec<u8> = contents.split_whitespace()
+                                  .take(num_labels)
+                                  .map(|x| x.parse().expect("Could not parse label."))
+                                  .collect();
+
+    if labels.len() < num_labels {
+        panic!("[ERROR] read_labels: label file does not contain a label for each trace.");
+    }
+
+    if labels.iter().any(|&x| x > 1) {
+        panic!("[ERROR] read_labels: labels must be 0 or 1.");
+    }
+
+    labels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use tables::S;
+
+    /// Writes a table file to the temporary directory and reads it back
+    fn write_and_read(name: &str, contents: &[u8]) -> Result<[u8;256], String> {
+        let path = env::temp_dir().join(format!("hodca_table_{}", name));
+        fs::write(&path, contents).expect("Could not write table.");
+
+        let table = read_table(path.to_str().unwrap());
+        fs::remove_file(&path).expect("Could not remove table.");
+
+        table
+    }
+
+    #[test]
+    fn table_formats() {
+        let entries: Vec<String> = S.iter().map(|x| format!("0x{:02x}", x)).collect();
+        let text = format!("# AES S-box\n{}\n", entries.join(", "));
+        let string: String = S.iter().map(|x| format!("{:02x}", x)).collect();
+
+        assert_eq!(write_and_read("binary", &S).unwrap().to_vec(), S.to_vec());
+        assert_eq!(write_and_read("text", text.as_bytes()).unwrap().to_vec(), S.to_vec());
+        assert_eq!(write_and_read("string", string.as_bytes()).unwrap().to_vec(), S.to_vec());
+        assert!(write_and_read("short", b"00 01 02").is_err());
+    }
+
+    #[test]
+    fn trace_set_dimensions() {
+        let dir = env::temp_dir();
+        let path = |name: &str| dir.join(name).to_str().unwrap().to_owned();
+        let dimensions = |name: &str, traces, length| {
+            trace_dimensions(&path(name), traces, length, TraceFormat::Daredevil, 16)
+        };
+
+        // 4 traces of 8 samples, named as 4 traces of 8 and as 2 traces of 16 samples
+        for name in &["hodca_dims_4_8", "hodca_dims_2_16", "hodca_dims"] {
+            fs::write(path(name) + ".trace", [0u8;32]).expect("Could not write traces.");
+        }
+
+        fs::write(path("hodca_dims_4_8") + ".input", [0u8;64]).expect("Could not write inputs.");
+
+        assert_eq!(dimensions("hodca_dims_4_8", None, None), Ok((4, 8)));
+        assert_eq!(dimensions("hodca_dims_4_8", Some(2), None), Ok((2, 8)));
+        assert!(dimensions("hodca_dims_4_8", None, Some(16)).is_err());
+        assert!(dimensions("hodca_dims_2_16", None, Some(8)).is_err());
+        assert_eq!(dimensions("hodca_dims", Some(2), Some(8)), Ok((2, 8)));
+        assert!(dimensions("hodca_dims", Some(2), None).is_err());
+
+        for name in &["hodca_dims_4_8", "hodca_dims_2_16", "hodca_dims"] {
+            fs::remove_file(path(name) + ".trace").expect("Could not remove traces.");
+        }
+
+        fs::remove_file(path("hodca_dims_4_8") + ".input").expect("Could not remove inputs.");
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::BTreeSet;
+use std::fs;
+use options::DataType;
+
+/// A region of interest is a list of sample ranges. Each range includes its start and excludes
+/// its stop.
+pub type Region = Vec<(usize,usize)>;
+
+/// Parses a list of ranges of the form `100-200,300-400`.
+pub fn parse_ranges(ranges: &str) -> Result<Region, String> {
+    let mut region = vec![];
+
+    for range in ranges.split(',').map(|r| r.trim()).filter(|r| !r.is_empty()) {
+        let bounds: Vec<&str> = range.split('-').collect();
+
+        if bounds.len() != 2 {
+            return Err(format!("{:?} is not a valid range.", range));
+        }
+
+        let start = bounds[0].trim().parse().map_err(|_| format!("{:?} is not a valid range.", range))?;
+        let stop = bounds[1].trim().parse().map_err(|_| format!("{:?} is not a valid range.", range))?;
+
+        if start > stop {
+            return Err(format!("Start index is larger than stop index in range {:?}.", range));
+        }
+
+        region.push((start, stop));
+    }
+
+    if region.is_empty() {
+        return Err(format!("{:?} does not contain any ranges.", ranges));
+    }
+
+    Ok(region)
+}
+
+/// Parses the region of a single key byte, given as `<byte>:<ranges>`, e.g. `3:100-200,300-400`.
+pub fn parse_region(region: &str) -> Result<(usize, Region), String> {
+    let mut parts = region.splitn(2, ':');
+    let byte = parts.next().unwrap_or("").trim();
+    let ranges = parts.next().ok_or(format!("{:?} is not a valid region.", region))?;
+    let byte = byte.parse().map_err(|_| format!("{:?} is not a valid key byte.", byte))?;
+
+    Ok((byte, parse_ranges(ranges)?))
+}
+
+/// Builds a region for each of `num_positions` key bytes. Each element of `regions` overrides the
+/// default bounds of a single key byte, listing the same key byte more than once adds ranges.
+/// Each region must lie within traces of `length` samples and contain samples of `data_type`.
+pub fn build_regions(default: (usize,usize),
+                     num_positions: usize,
+                     regions: &[(usize, Region)],
+                     length: usize,
+                     data_type: DataType)
+                     -> Result<Vec<Region>, String> {
+    let mut result: Vec<Region> = vec![vec![]; num_positions];
+
+    for (byte, region) in regions {
+        if *byte >= num_positions {
+            return Err(format!("Key byte {} is out of range.", byte));
+        }
+
+        result[*byte].extend(region);
+    }
+
+    for (byte, region) in result.iter_mut().enumerate() {
+        if region.is_empty() {
+            region.push(default);
+        }
+
+        if region.iter().any(|r| r.1 > length) {
+            return Err(format!("Region of key byte {} ({}) exceeds the trace length {}.", byte,
+                               format_region(region), length));
+        }
+
+        if region_samples(region, data_type).is_empty() {
+            return Err(format!("Region of key byte {} ({}) does not contain any samples.", byte,
+                               format_region(region)));
+        }
+    }
+
+    Ok(result)
+}
+
+/// Reads per key byte regions from file. Each line holds the region of one key byte in the format
+/// accepted by `parse_region`. Empty lines and lines starting with `#` are ignored.
+pub fn read_regions(region_path: &str) -> Result<Vec<(usize, Region)>, String> {
+    let contents = fs::read_to_string(region_path)
+                      .map_err(|e| format!("Could not read {}: {}", region_path, e))?;
+
+    contents.lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(parse_region)
+            .collect()
+}
+
+/// Returns the sorted and distinct sample indices covered by a region. Ranges are given in bits
+/// and are converted to byte indices for `DataType::Bytes`.
+pub fn region_samples(region: &Region, data_type: DataType) -> Vec<usize> {
+    let mut samples = BTreeSet::new();
+
+    for &(start, stop) in region {
+        let (start, stop) = match data_type {
+            DataType::Bytes => (start/8, stop/8),
+            _               => (start, stop),
+        };
+
+        samples.extend(start..stop);
+    }
+
+    samples.into_iter().collect()
+}
+
+/// Formats a region for printing
+pub fn format_region(region: &Region) -> String {
+    let ranges: Vec<String> = region.iter().map(|r| format!("{} -> {}", r.0, r.1)).collect();
+    ranges.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn regions_within_traces() {
+        let regions = vec![(1, vec![(0, 64)]), (2, vec![(8, 12)])];
+
+        assert!(build_regions((0, 64), 4, &regions[..1], 64, DataType::Bits).is_ok());
+        assert_eq!(build_regions((0, 64), 4, &regions[..1], 32, DataType::Bits),
+                   Err("Region of key byte 0 (0 -> 64) exceeds the trace length 32.".to_owned()));
+        assert_eq!(build_regions((0, 64), 4, &regions, 64, DataType::Bytes),
+                   Err("Region of key byte 2 (8 -> 12) does not contain any samples.".to_owned()));
+    }
+}
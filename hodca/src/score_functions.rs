@@ -1,29 +1,33 @@
 use num_cpus;
+use std::cmp;
 use std::thread;
 use std::sync::mpsc;
 use num::Float;
 use tuple_iterator::{TupleIterator,WindowedTupleIterator};
 use readers::Trace;
 use correlation_functions::*;
+use gf2::{Basis, BitVector, pack_bits, ones};
 
-/// Type for holding the score and key value
-pub type KeyScores = [(f64, usize); 256];
+/// Type for holding the score and key value of each guess
+pub type KeyScores = Vec<(f64, usize)>;
 
 /// Calculates pearson scores for a trace
-pub fn pearson_scores(bounds: (usize,usize), 
+pub fn pearson_scores(samples: &[usize], 
                       window: usize, 
                       order: usize,
-                      traces: &Vec<Trace>, 
-                      guesses: &Vec<Vec<u8>>) 
+                      traces: &[Trace], 
+                      guesses: &[Vec<u8>]) 
                       -> KeyScores {
+    let num_guesses = guesses.len();
+
     // We assume that all guesses as the same length. This is true if they are
     // generated using generate_guesses
     let guess_len = guesses[0].len();
 
     // Calculate auxilliary information about the bit guesses to speed up correlation calculations
-    let mut aux_values = [(0.0,0.0);256];
+    let mut aux_values = vec![(0.0,0.0);num_guesses];
 
-    for i in 0..256 {
+    for i in 0..num_guesses {
         aux_values[i] = get_auxilliary_values(&guesses[i]);
     }
 
@@ -31,15 +35,15 @@ pub fn pearson_scores(bounds: (usize,usize),
     let (result_tx,result_rx) = mpsc::channel();
 
     for t in 0..num_threads {
-        let (result_tx, aux_values, guesses,traces) = 
-            (result_tx.clone(), aux_values,guesses.clone(), traces.clone());
+        let (result_tx, aux_values, guesses, traces, samples) = 
+            (result_tx.clone(), aux_values.clone(), guesses.to_vec(), traces.to_vec(), samples.to_vec());
 
         thread::spawn(move || {
-            let mut correlations = [(0.0,0);256];
+            let mut correlations = vec![(0.0,0);num_guesses];
 
-            // Iterate over all tuples time points, but skipping num_threads each time
+            // Iterate over all tuples of sample positions, but skipping num_threads each time
             // and having an offset of the current thread index
-            let range_size = bounds.1 - bounds.0;
+            let range_size = samples.len();
             let time_tuples: Vec<Vec<usize>>;
 
             if order == 1 {
@@ -57,7 +61,7 @@ pub fn pearson_scores(bounds: (usize,usize),
                 
                 for i in 0..guess_len {
                     for time_point in &tuple {
-                        ho_trace[i] ^= traces[i][bounds.0 + *time_point];
+                        ho_trace[i] ^= traces[i][samples[*time_point]];
                     }
                 }
 
@@ -65,7 +69,7 @@ pub fn pearson_scores(bounds: (usize,usize),
                 let (s1,s2) = get_auxilliary_values(&ho_trace);
 
                 // Calculate correlation of second order trace for each guess
-                for i in 0..256 {
+                for i in 0..num_guesses {
                     let (s3,s4) = (aux_values[i].0,aux_values[i].1);
                     let c = double_assisted_pearson(&ho_trace,&guesses[i],
                                                  s1,s2,s3,s4);
@@ -81,13 +85,103 @@ pub fn pearson_scores(bounds: (usize,usize),
         });
     }
 
-    let mut key_scores = [(0.0,0);256];
+    let mut key_scores = vec![(0.0,0);num_guesses];
 
     for _ in 0..num_threads {
         let thread_result = result_rx.recv().expect("Main could not receive result");
 
         // Update current best correlations
-        for i in 0..256 {
+        for i in 0..num_guesses {
+            if thread_result[i].0.abs() > key_scores[i].0.abs() {
+                key_scores[i] = thread_result[i];
+            }
+        }
+    }
+
+    key_scores
+}
+
+/// Calculates difference of means scores for a trace. The guesses must be single bits, which
+/// partition the traces into two sets
+pub fn difference_of_means_scores(samples: &[usize], 
+                                  window: usize, 
+                                  order: usize,
+                                  traces: &[Trace], 
+                                  guesses: &[Vec<u8>]) 
+                                  -> KeyScores {
+    let num_guesses = guesses.len();
+
+    // We assume that all guesses as the same length. This is true if they are
+    // generated using generate_guesses
+    let guess_len = guesses[0].len();
+
+    // Count the ones of each guess, i.e. the size of one of the partitions
+    let mut ones = vec![0.0;num_guesses];
+
+    for i in 0..num_guesses {
+        if guesses[i].iter().any(|&x| x > 1) {
+            panic!("[ERROR] difference_of_means_scores: guesses must be single bits.");
+        }
+
+        ones[i] = get_auxilliary_values(&guesses[i]).0;
+    }
+
+    let num_threads = num_cpus::get();
+    let (result_tx,result_rx) = mpsc::channel();
+
+    for t in 0..num_threads {
+        let (result_tx, ones, guesses, traces, samples) = 
+            (result_tx.clone(), ones.clone(), guesses.to_vec(), traces.to_vec(), samples.to_vec());
+
+        thread::spawn(move || {
+            let mut differences = vec![(0.0,0);num_guesses];
+
+            // Iterate over all tuples of sample positions, but skipping num_threads each time
+            // and having an offset of the current thread index
+            let range_size = samples.len();
+            let time_tuples: Vec<Vec<usize>> = if order == 1 {
+                TupleIterator::new(order, range_size)
+                    .filter(|x| x.iter().sum::<usize>() % num_threads == t)
+                    .collect()
+            } else {
+                WindowedTupleIterator::new(order, range_size, window)
+                    .filter(|x| x.iter().sum::<usize>() % num_threads == t)
+                    .collect()
+            };
+
+            for tuple in time_tuples {
+                let mut ho_trace =  vec![0;guess_len];
+                
+                for i in 0..guess_len {
+                    for time_point in &tuple {
+                        ho_trace[i] ^= traces[i][samples[*time_point]];
+                    }
+                }
+
+                let (sum, _) = get_auxilliary_values(&ho_trace);
+
+                // Calculate difference of means of the higher order trace for each guess
+                for i in 0..num_guesses {
+                    let c = assisted_difference_of_means(&ho_trace, &guesses[i], sum, ones[i]);
+
+                    // Save guess if larger than current
+                    if c.abs() > differences[i].0.abs() {
+                        differences[i] = (c,i);
+                    }
+                }
+            }
+
+            result_tx.send(differences).expect("Thread could not send result");
+        });
+    }
+
+    let mut key_scores: KeyScores = (0..num_guesses).map(|i| (0.0, i)).collect();
+
+    for _ in 0..num_threads {
+        let thread_result = result_rx.recv().expect("Main could not receive result");
+
+        // Update current best differences
+        for i in 0..num_guesses {
             if thread_result[i].0.abs() > key_scores[i].0.abs() {
                 key_scores[i] = thread_result[i];
             }
@@ -98,12 +192,14 @@ pub fn pearson_scores(bounds: (usize,usize),
 }
 
 /// Calculates equality scores for a trace
-pub fn equality_scores(bounds: (usize,usize), 
+pub fn equality_scores(samples: &[usize], 
                        window: usize, 
                        order: usize,
-                       traces: &Vec<Trace>, 
-                       guesses: &Vec<Vec<u8>>) 
+                       traces: &[Trace], 
+                       guesses: &[Vec<u8>]) 
                        -> KeyScores {
+    let num_guesses = guesses.len();
+
     // We assume that all guesses as the same length. This is true if they are
     // generated using generate_guesses
     let guess_len = guesses[0].len();
@@ -112,14 +208,15 @@ pub fn equality_scores(bounds: (usize,usize),
     let (result_tx,result_rx) = mpsc::channel();
 
     for t in 0..num_threads {
-        let (result_tx, guesses, traces) = (result_tx.clone(), guesses.clone(), traces.clone());
+        let (result_tx, guesses, traces, samples) = 
+            (result_tx.clone(), guesses.to_vec(), traces.to_vec(), samples.to_vec());
 
         thread::spawn(move || {
-            let mut counters = [(0.0,0);256];
+            let mut counters = vec![(0.0,0);num_guesses];
 
-            // Iterate over all tuples time points, but skipping num_threads each time
+            // Iterate over all tuples of sample positions, but skipping num_threads each time
             // and having an offset of the current thread index
-            let range_size = bounds.1 - bounds.0;
+            let range_size = samples.len();
             let time_tuples: Vec<Vec<usize>>;
 
             if order == 1 {
@@ -137,12 +234,12 @@ pub fn equality_scores(bounds: (usize,usize),
                 
                 for i in 0..guess_len {
                     for time_point in &tuple {
-                        ho_trace[i] ^= traces[i][bounds.0 + *time_point];
+                        ho_trace[i] ^= traces[i][samples[*time_point]];
                     }
                 }
 
                 // Calculate correlation of second order trace for each guess
-                for i in 0..256 {
+                for i in 0..num_guesses {
                     let c = equality_correlation(&ho_trace, &guesses[i]);
 
                     // Save guess if larger than current
@@ -156,13 +253,13 @@ pub fn equality_scores(bounds: (usize,usize),
         });
     }
 
-    let mut key_scores = [(0.0,0);256];
+    let mut key_scores = vec![(0.0,0);num_guesses];
 
     for _ in 0..num_threads {
         let thread_result = result_rx.recv().expect("Main could not receive result");
 
         // Update current best counter
-        for i in 0..256 {
+        for i in 0..num_guesses {
             if thread_result[i].0.abs() > key_scores[i].0.abs() {
                 key_scores[i] = thread_result[i];
             }
@@ -173,12 +270,14 @@ pub fn equality_scores(bounds: (usize,usize),
 }
 
 /// Calculates likelihood scores for a trace.
-pub fn likelihood_scores(bounds: (usize,usize), 
+pub fn likelihood_scores(samples: &[usize], 
                          window: usize, 
                          order: usize,
-                         traces: &Vec<Trace>, 
-                         guesses: &Vec<Vec<u8>>) 
+                         traces: &[Trace], 
+                         guesses: &[Vec<u8>]) 
                          -> KeyScores {
+    let num_guesses = guesses.len();
+
     // We assume that all guesses as the same length. This is true if they are
     // generated using generate_guesses
     let guess_len = guesses[0].len();
@@ -188,14 +287,15 @@ pub fn likelihood_scores(bounds: (usize,usize),
     let (result_tx,result_rx) = mpsc::channel();
 
     for t in 0..num_threads {
-        let (result_tx,guesses,traces) = (result_tx.clone(),guesses.clone(),traces.clone());
+        let (result_tx, guesses, traces, samples) = 
+            (result_tx.clone(), guesses.to_vec(), traces.to_vec(), samples.to_vec());
 
         thread::spawn(move || {
-            let mut counters = vec![vec![0;guess_len];256];
+            let mut counters = vec![vec![0;guess_len];num_guesses];
 
-            // Iterate over all tuples time points, but skipping num_threads each time
+            // Iterate over all tuples of sample positions, but skipping num_threads each time
             // and having an offset of the current thread index
-            let range_size = bounds.1 - bounds.0;
+            let range_size = samples.len();
             let time_tuples: Vec<Vec<usize>>;
             
             if order == 1 {
@@ -213,12 +313,12 @@ pub fn likelihood_scores(bounds: (usize,usize),
                 
                 for i in 0..guess_len {
                     for time_point in &tuple {
-                        ho_trace[i] ^= traces[i][bounds.0 + *time_point];
+                        ho_trace[i] ^= traces[i][samples[*time_point]];
                     }
                 }
 
                 // Calculate correlation of second order trace for each guess
-                for i in 0..256 {
+                for i in 0..num_guesses {
                     add_loglikelihood_counters(&ho_trace,&guesses[i],&mut counters[i]);
                 }
             }
@@ -227,14 +327,14 @@ pub fn likelihood_scores(bounds: (usize,usize),
         });
     }    
     
-    let mut counters = vec![vec![0;guess_len];256];
-    let mut key_scores = [(0.0,0);256];
+    let mut counters = vec![vec![0;guess_len];num_guesses];
+    let mut key_scores = vec![(0.0,0);num_guesses];
 
     for _ in 0..num_threads {
         let thread_result = result_rx.recv().expect("Main could not receive result");
 
         // Add result to counters
-        for i in 0..256 {
+        for i in 0..num_guesses {
             for j in 0..guess_len {
                 counters[i][j] += thread_result[i][j];
             }
@@ -242,10 +342,280 @@ pub fn likelihood_scores(bounds: (usize,usize),
     }
 
     // Calculate correlations
-    for i in 0..256 {
+    for i in 0..num_guesses {
         let likelihood = loglikelihood_correlation(&counters[i]);
         key_scores[i] = (likelihood,i);
     }
 
     key_scores
 }
+
+/// Calculates mutual information scores for a trace
+pub fn mutual_information_scores(samples: &[usize], 
+                                 window: usize, 
+                                 order: usize,
+                                 traces: &[Trace], 
+                                 guesses: &[Vec<u8>]) 
+                                 -> KeyScores {
+    let num_guesses = guesses.len();
+
+    // We assume that all guesses as the same length. This is true if they are
+    // generated using generate_guesses
+    let guess_len = guesses[0].len();
+
+    // Calculate the entropy of the guesses to speed up the mutual information calculations
+    let mut guess_entropy = vec![0.0;num_guesses];
+
+    for i in 0..num_guesses {
+        guess_entropy[i] = entropy(&guesses[i]);
+    }
+
+    let num_threads = num_cpus::get();
+    let (result_tx,result_rx) = mpsc::channel();
+
+    for t in 0..num_threads {
+        let (result_tx, guess_entropy, guesses, traces, samples) = 
+            (result_tx.clone(), guess_entropy.clone(), guesses.to_vec(), traces.to_vec(), samples.to_vec());
+
+        thread::spawn(move || {
+            let mut scores = vec![(0.0,0);num_guesses];
+
+            // Iterate over all tuples of sample positions, but skipping num_threads each time
+            // and having an offset of the current thread index
+            let range_size = samples.len();
+            let time_tuples: Vec<Vec<usize>> = if order == 1 {
+                TupleIterator::new(order, range_size)
+                    .filter(|x| x.iter().sum::<usize>() % num_threads == t)
+                    .collect()
+            } else {
+                WindowedTupleIterator::new(order, range_size, window)
+                    .filter(|x| x.iter().sum::<usize>() % num_threads == t)
+                    .collect()
+            };
+
+            for tuple in time_tuples {
+                let mut ho_trace =  vec![0;guess_len];
+                
+                for i in 0..guess_len {
+                    for time_point in &tuple {
+                        ho_trace[i] ^= traces[i][samples[*time_point]];
+                    }
+                }
+
+                // A constant trace carries no information about any guess
+                let ho_entropy = entropy(&ho_trace);
+
+                if ho_entropy == 0.0 {
+                    continue;
+                }
+
+                // Calculate mutual information of the higher order trace for each guess
+                for i in 0..num_guesses {
+                    let c = assisted_mutual_information(&ho_trace, &guesses[i],
+                                                        ho_entropy, guess_entropy[i]);
+
+                    // Save guess if larger than current
+                    if c > scores[i].0 {
+                        scores[i] = (c,i);
+                    }
+                }
+            }
+
+            result_tx.send(scores).expect("Thread could not send result");
+        });
+    }
+
+    let mut key_scores: KeyScores = (0..num_guesses).map(|i| (0.0, i)).collect();
+
+    for _ in 0..num_threads {
+        let thread_result = result_rx.recv().expect("Main could not receive result");
+
+        // Update current best scores
+        for i in 0..num_guesses {
+            if thread_result[i].0 > key_scores[i].0 {
+                key_scores[i] = thread_result[i];
+            }
+        }
+    }
+
+    key_scores
+}
+
+/// Splits a vector of bytes into its low and high nibbles. Constant nibbles are left out, as
+/// they are independent of anything
+fn get_nibbles(x: &[u8]) -> Vec<Vec<u8>> {
+    let nibbles: Vec<Vec<u8>> = vec![x.iter().map(|v| v & 0xf).collect(), 
+                                     x.iter().map(|v| v >> 4).collect()];
+
+    nibbles.into_iter().filter(|n| n.iter().any(|&v| v != n[0])).collect()
+}
+
+/// Calculates chi-squared scores for a trace. The contingency tables are reduced to nibbles: the
+/// score is the largest statistic between a nibble of the higher order trace and a nibble of the
+/// guess, which detects any bijective encoding of the nibbles of the target. For bits, this is
+/// the statistic of the 2x2 table
+pub fn chi_squared_scores(samples: &[usize], 
+                          window: usize, 
+                          order: usize,
+                          traces: &[Trace], 
+                          guesses: &[Vec<u8>]) 
+                          -> KeyScores {
+    let num_guesses = guesses.len();
+
+    // We assume that all guesses as the same length. This is true if they are
+    // generated using generate_guesses
+    let guess_len = guesses[0].len();
+
+    // Split the guesses into nibbles once
+    let guess_nibbles: Vec<Vec<Vec<u8>>> = guesses.iter().map(|g| get_nibbles(g)).collect();
+
+    let num_threads = num_cpus::get();
+    let (result_tx,result_rx) = mpsc::channel();
+
+    for t in 0..num_threads {
+        let (result_tx, guess_nibbles, traces, samples) = 
+            (result_tx.clone(), guess_nibbles.clone(), traces.to_vec(), samples.to_vec());
+
+        thread::spawn(move || {
+            let mut scores = vec![(0.0,0);num_guesses];
+
+            // Iterate over all tuples of sample positions, but skipping num_threads each time
+            // and having an offset of the current thread index
+            let range_size = samples.len();
+            let time_tuples: Vec<Vec<usize>> = if order == 1 {
+                TupleIterator::new(order, range_size)
+                    .filter(|x| x.iter().sum::<usize>() % num_threads == t)
+                    .collect()
+            } else {
+                WindowedTupleIterator::new(order, range_size, window)
+                    .filter(|x| x.iter().sum::<usize>() % num_threads == t)
+                    .collect()
+            };
+
+            for tuple in time_tuples {
+                let mut ho_trace =  vec![0;guess_len];
+                
+                for i in 0..guess_len {
+                    for time_point in &tuple {
+                        ho_trace[i] ^= traces[i][samples[*time_point]];
+                    }
+                }
+
+                let ho_nibbles = get_nibbles(&ho_trace);
+
+                // Calculate the statistic of each pair of nibbles for each guess
+                for i in 0..num_guesses {
+                    for x in &ho_nibbles {
+                        for y in &guess_nibbles[i] {
+                            let c = chi_squared_statistic(x, y);
+
+                            // Save guess if larger than current
+                            if c > scores[i].0 {
+                                scores[i] = (c,i);
+                            }
+                        }
+                    }
+                }
+            }
+
+            result_tx.send(scores).expect("Thread could not send result");
+        });
+    }
+
+    let mut key_scores: KeyScores = (0..num_guesses).map(|i| (0.0, i)).collect();
+
+    for _ in 0..num_threads {
+        let thread_result = result_rx.recv().expect("Main could not receive result");
+
+        // Update current best scores
+        for i in 0..num_guesses {
+            if thread_result[i].0 > key_scores[i].0 {
+                key_scores[i] = thread_result[i];
+            }
+        }
+    }
+
+    key_scores
+}
+
+/// Calculates linear decoding scores for a trace. For each window of samples, a guess scores one
+/// if it lies in the span of the samples of the window and the constant one, i.e. if it is an
+/// affine function of them. Windows overlap by half their size, and the order is not used since
+/// the span already covers all combinations of samples. The traces and guesses must be bits, and
+/// there must be at least twice window + 1 traces: with fewer, the window and the constant one
+/// span nearly every vector, and all guesses decode
+pub fn linear_decoding_scores(samples: &[usize], 
+                              window: usize, 
+                              traces: &[Trace], 
+                              guesses: &[Vec<u8>]) 
+                              -> KeyScores {
+    let num_guesses = guesses.len();
+
+    // We assume that all guesses as the same length. This is true if they are
+    // generated using generate_guesses
+    let guess_len = guesses[0].len();
+
+    // Pack guesses and sample columns to do Gaussian elimination on whole words
+    let packed_guesses: Vec<BitVector> = guesses.iter().map(|g| pack_bits(g)).collect();
+    let columns: Vec<BitVector> = samples.iter()
+                                         .map(|&s| pack_bits(&traces.iter().map(|t| t[s]).collect::<Vec<u8>>()))
+                                         .collect();
+
+    // Windows start every half window, until a window reaches the last sample
+    let step = cmp::max(1, window/2);
+    let mut window_starts = vec![0];
+
+    while window_starts[window_starts.len()-1] + window < samples.len() {
+        let next = window_starts[window_starts.len()-1] + step;
+        window_starts.push(next);
+    }
+
+    let num_threads = num_cpus::get();
+    let (result_tx,result_rx) = mpsc::channel();
+
+    for t in 0..num_threads {
+        let (result_tx, packed_guesses, columns, window_starts) = 
+            (result_tx.clone(), packed_guesses.clone(), columns.clone(), window_starts.clone());
+
+        thread::spawn(move || {
+            let mut scores = vec![(0.0,0);num_guesses];
+
+            // Iterate over all windows, but skipping num_threads each time and having an offset
+            // of the current thread index
+            for &start in window_starts.iter().skip(t).step_by(num_threads) {
+                let stop = cmp::min(start + window, columns.len());
+                let mut basis = Basis::new();
+
+                basis.insert(&ones(guess_len));
+
+                for column in &columns[start..stop] {
+                    basis.insert(column);
+                }
+
+                // Check if each guess is an affine function of the window
+                for i in 0..num_guesses {
+                    if scores[i].0 == 0.0 && basis.contains(&packed_guesses[i]) {
+                        scores[i] = (1.0,i);
+                    }
+                }
+            }
+
+            result_tx.send(scores).expect("Thread could not send result");
+        });
+    }
+
+    let mut key_scores: KeyScores = (0..num_guesses).map(|i| (0.0, i)).collect();
+
+    for _ in 0..num_threads {
+        let thread_result = result_rx.recv().expect("Main could not receive result");
+
+        // A guess scores if it was decoded in any window
+        for i in 0..num_guesses {
+            if thread_result[i].0 > key_scores[i].0 {
+                key_scores[i] = thread_result[i];
+            }
+        }
+    }
+
+    key_scores
+}
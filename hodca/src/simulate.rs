@@ -0,0 +1,77 @@
+use tables::S;
+use readers::Trace;
+
+/// A small xorshift64* pseudo-random number generator. Not cryptographically secure, but good
+/// enough to generate synthetic traces.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Creates a new generator from a seed. A zero seed is replaced by a fixed non-zero constant.
+    pub fn new(seed: u64) -> Rng {
+        Rng {
+            state: if seed == 0 { 0x9e37_79b9_7f4a_7c15 } else { seed },
+        }
+    }
+
+    /// Returns the next 64 pseudo-random bits
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Returns the next pseudo-random byte
+    pub fn next_u8(&mut self) -> u8 {
+        (self.next_u64() >> 56) as u8
+    }
+}
+
+/// Appends the bits of a byte to a trace as eight samples, least significant bit first
+fn push_byte(trace: &mut Trace, byte: u8) {
+    for b in 0..8 {
+        trace.push((byte >> b) & 0x1);
+    }
+}
+
+/// Generates bit-serialized traces of the first round S-box outputs of AES with random inputs.
+/// Each S-box output is split into `masking_order+1` Boolean shares, and preceded by `noise`
+/// random bytes. Returns the traces and the inputs used.
+pub fn simulate_traces(num_traces: usize,
+                       key: &[u8;16],
+                       masking_order: usize,
+                       noise: usize,
+                       seed: u64)
+                       -> (Vec<Trace>, Vec<Vec<u8>>) {
+    let mut rng = Rng::new(seed);
+    let mut traces = Vec::with_capacity(num_traces);
+    let mut inputs = Vec::with_capacity(num_traces);
+
+    for _ in 0..num_traces {
+        let input: Vec<u8> = (0..16).map(|_| rng.next_u8()).collect();
+        let mut trace = vec![];
+
+        for k in 0..16 {
+            for _ in 0..noise {
+                let byte = rng.next_u8();
+                push_byte(&mut trace, byte);
+            }
+
+            let masks: Vec<u8> = (0..masking_order).map(|_| rng.next_u8()).collect();
+            let share = masks.iter().fold(S[(input[k] ^ key[k]) as usize], |acc, &m| acc ^ m);
+
+            push_byte(&mut trace, share);
+
+            for &m in &masks {
+                push_byte(&mut trace, m);
+            }
+        }
+
+        traces.push(trace);
+        inputs.push(input);
+    }
+
+    (traces, inputs)
+}
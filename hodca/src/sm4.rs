@@ -0,0 +1,152 @@
+/// The SM4 S-box
+pub static SBOX: [u8;256] = [
+    0xd6, 0x90, 0xe9, 0xfe, 0xcc, 0xe1, 0x3d, 0xb7, 0x16, 0xb6, 0x14, 0xc2, 0x28, 0xfb, 0x2c, 0x05,
+    0x2b, 0x67, 0x9a, 0x76, 0x2a, 0xbe, 0x04, 0xc3, 0xaa, 0x44, 0x13, 0x26, 0x49, 0x86, 0x06, 0x99,
+    0x9c, 0x42, 0x50, 0xf4, 0x91, 0xef, 0x98, 0x7a, 0x33, 0x54, 0x0b, 0x43, 0xed, 0xcf, 0xac, 0x62,
+    0xe4, 0xb3, 0x1c, 0xa9, 0xc9, 0x08, 0xe8, 0x95, 0x80, 0xdf, 0x94, 0xfa, 0x75, 0x8f, 0x3f, 0xa6,
+    0x47, 0x07, 0xa7, 0xfc, 0xf3, 0x73, 0x17, 0xba, 0x83, 0x59, 0x3c, 0x19, 0xe6, 0x85, 0x4f, 0xa8,
+    0x68, 0x6b, 0x81, 0xb2, 0x71, 0x64, 0xda, 0x8b, 0xf8, 0xeb, 0x0f, 0x4b, 0x70, 0x56, 0x9d, 0x35,
+    0x1e, 0x24, 0x0e, 0x5e, 0x63, 0x58, 0xd1, 0xa2, 0x25, 0x22, 0x7c, 0x3b, 0x01, 0x21, 0x78, 0x87,
+    0xd4, 0x00, 0x46, 0x57, 0x9f, 0xd3, 0x27, 0x52, 0x4c, 0x36, 0x02, 0xe7, 0xa0, 0xc4, 0xc8, 0x9e,
+    0xea, 0xbf, 0x8a, 0xd2, 0x40, 0xc7, 0x38, 0xb5, 0xa3, 0xf7, 0xf2, 0xce, 0xf9, 0x61, 0x15, 0xa1,
+    0xe0, 0xae, 0x5d, 0xa4, 0x9b, 0x34, 0x1a, 0x55, 0xad, 0x93, 0x32, 0x30, 0xf5, 0x8c, 0xb1, 0xe3,
+    0x1d, 0xf6, 0xe2, 0x2e, 0x82, 0x66, 0xca, 0x60, 0xc0, 0x29, 0x23, 0xab, 0x0d, 0x53, 0x4e, 0x6f,
+    0xd5, 0xdb, 0x37, 0x45, 0xde, 0xfd, 0x8e, 0x2f, 0x03, 0xff, 0x6a, 0x72, 0x6d, 0x6c, 0x5b, 0x51,
+    0x8d, 0x1b, 0xaf, 0x92, 0xbb, 0xdd, 0xbc, 0x7f, 0x11, 0xd9, 0x5c, 0x41, 0x1f, 0x10, 0x5a, 0xd8,
+    0x0a, 0xc1, 0x31, 0x88, 0xa5, 0xcd, 0x7b, 0xbd, 0x2d, 0x74, 0xd0, 0x12, 0xb8, 0xe5, 0xb4, 0xb0,
+    0x89, 0x69, 0x97, 0x4a, 0x0c, 0x96, 0x77, 0x7e, 0x65, 0xb9, 0xf1, 0x09, 0xc5, 0x6e, 0xc6, 0x84,
+    0x18, 0xf0, 0x7d, 0xec, 0x3a, 0xdc, 0x4d, 0x20, 0x79, 0xee, 0x5f, 0x3e, 0xd7, 0xcb, 0x39, 0x48];
+
+/// The system parameter added to the key before the key schedule
+static FK: [u32;4] = [0xa3b1bac6, 0x56aa3350, 0x677d9197, 0xb27022dc];
+
+/// Applies the S-box to each byte of a word
+fn tau(x: u32) -> u32 {
+    (0..4).fold(0, |acc, b| (acc << 8) | SBOX[((x >> (24 - 8*b)) & 0xff) as usize] as u32)
+}
+
+/// The linear transform of the round function
+pub fn linear(x: u32) -> u32 {
+    x ^ x.rotate_left(2) ^ x.rotate_left(10) ^ x.rotate_left(18) ^ x.rotate_left(24)
+}
+
+/// The linear transform of the key schedule
+fn linear_key(x: u32) -> u32 {
+    x ^ x.rotate_left(13) ^ x.rotate_left(23)
+}
+
+/// The constants of the key schedule, CK_i,j = (4i + j) * 7 mod 256
+fn ck(round: usize) -> u32 {
+    (0..4).fold(0, |acc, j| (acc << 8) | ((28*round + 7*j) % 256) as u32)
+}
+
+/// Converts 16 bytes into four big-endian words
+pub fn block_words(bytes: &[u8]) -> [u32;4] {
+    if bytes.len() != 16 {
+        panic!("[ERROR] block_words: SM4 blocks are 16 bytes.");
+    }
+
+    let mut words = [0u32;4];
+
+    for (i, word) in words.iter_mut().enumerate() {
+        *word = bytes[4*i..4*i+4].iter().fold(0, |acc, &b| (acc << 8) | b as u32);
+    }
+
+    words
+}
+
+/// Converts four words into 16 bytes, the inverse of `block_words`
+pub fn block_bytes(words: &[u32;4]) -> Vec<u8> {
+    words.iter().flat_map(|w| (0..4).map(move |j| (w >> (24 - 8*j)) as u8)).collect()
+}
+
+/// Byte `position` of the input of the S-boxes of the first round, before the key is added
+pub fn round_chunk(state: &[u32;4], position: usize) -> u8 {
+    ((state[1] ^ state[2] ^ state[3]) >> (24 - 8*position)) as u8
+}
+
+/// The contribution of S-box `position` to the output of the linear transform, if the S-box
+/// outputs `value`
+pub fn linear_contribution(value: u8, position: usize) -> u32 {
+    linear((value as u32) << (24 - 8*position))
+}
+
+/// Combines the key bytes found by `attack_all` into a round key, key byte 0 being the most
+/// significant
+pub fn round_key(key_bytes: &[usize]) -> u32 {
+    key_bytes.iter().fold(0, |acc, &k| (acc << 8) | k as u32)
+}
+
+/// Applies rounds to a state with the given round keys
+pub fn rounds(state: &[u32;4], round_keys: &[u32]) -> [u32;4] {
+    let mut x = *state;
+
+    for &rk in round_keys {
+        let next = x[0] ^ linear(tau(x[1] ^ x[2] ^ x[3] ^ rk));
+        x = [x[1], x[2], x[3], next];
+    }
+
+    x
+}
+
+/// Derives the 32 round keys of a key
+pub fn key_schedule(key: &[u32;4]) -> Vec<u32> {
+    let mut k: Vec<u32> = (0..4).map(|i| key[i] ^ FK[i]).collect();
+
+    for i in 0..32 {
+        let next = k[i] ^ linear_key(tau(k[i+1] ^ k[i+2] ^ k[i+3] ^ ck(i)));
+        k.push(next);
+    }
+
+    k[4..].to_vec()
+}
+
+/// Encrypts a single block with SM4
+pub fn encrypt(key: &[u32;4], plaintext: &[u32;4]) -> [u32;4] {
+    let x = rounds(plaintext, &key_schedule(key));
+
+    // The output is the final four words in reverse order
+    [x[3], x[2], x[1], x[0]]
+}
+
+/// Reconstructs the key from the first four round keys, by running the key schedule backwards
+pub fn invert_key_schedule(round_keys: &[u32;4]) -> [u32;4] {
+    let mut k = [0u32;8];
+    k[4..].copy_from_slice(round_keys);
+
+    for i in (0..4).rev() {
+        k[i] = k[i+4] ^ linear_key(tau(k[i+1] ^ k[i+2] ^ k[i+3] ^ ck(i)));
+    }
+
+    [k[0] ^ FK[0], k[1] ^ FK[1], k[2] ^ FK[2], k[3] ^ FK[3]]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The key and plaintext of the example of the SM4 standard
+    const KEY: [u32;4] = [0x01234567, 0x89abcdef, 0xfedcba98, 0x76543210];
+
+    #[test]
+    fn encrypt_known_answer() {
+        assert_eq!(encrypt(&KEY, &KEY), [0x681edf34, 0xd206965e, 0x86b3e94f, 0x536e4246]);
+    }
+
+    #[test]
+    fn key_schedule_inversion() {
+        let round_keys = key_schedule(&KEY);
+
+        assert_eq!(round_keys[..4], [0xf12186f9, 0x41662b61, 0x5a6ab19a, 0x7ba92077]);
+        assert_eq!(invert_key_schedule(&[round_keys[0], round_keys[1], round_keys[2],
+                                         round_keys[3]]), KEY);
+    }
+
+    #[test]
+    fn block_conversion() {
+        let bytes: Vec<u8> = (0..16).map(|i| 0x11 * i).collect();
+
+        assert_eq!(block_words(&bytes), [0x00112233, 0x44556677, 0x8899aabb, 0xccddeeff]);
+        assert_eq!(block_bytes(&block_words(&bytes)), bytes);
+    }
+}
@@ -0,0 +1,102 @@
+use num_cpus;
+use std::thread;
+use std::sync::mpsc;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use tuple_iterator::{TupleIterator,WindowedTupleIterator};
+use readers::Trace;
+use correlation_functions::welch_t_statistic;
+
+/// A tuple of sample indices and its t-statistic
+pub type TupleStatistic = (Vec<usize>, f64);
+
+/// Calculates the Welch t-statistic between the traces labelled zero and the traces labelled one
+/// for all (windowed) tuples of `samples`, where the samples of a tuple are combined with XOR.
+/// Returns the tuples whose statistic exceeds `threshold` in absolute value, sorted by decreasing
+/// absolute value, and the largest absolute value found.
+pub fn welch_t_test(samples: &[usize], 
+                    window: usize, 
+                    order: usize,
+                    threshold: f64,
+                    traces: &[Trace], 
+                    labels: &[u8]) 
+                    -> (Vec<TupleStatistic>, f64) {
+    if traces.len() != labels.len() {
+        panic!("[ERROR] welch_t_test: there must be a label for each trace.");
+    }
+
+    let num_traces = traces.len();
+    let num_threads = num_cpus::get();
+    let (result_tx,result_rx) = mpsc::channel();
+
+    for t in 0..num_threads {
+        let (result_tx, labels, traces, samples) = 
+            (result_tx.clone(), labels.to_vec(), traces.to_vec(), samples.to_vec());
+
+        thread::spawn(move || {
+            let mut leaking = vec![];
+            let mut max_statistic: f64 = 0.0;
+
+            // Iterate over all tuples of sample positions, but skipping num_threads each time
+            // and having an offset of the current thread index
+            let range_size = samples.len();
+            let time_tuples: Vec<Vec<usize>> = if order == 1 {
+                TupleIterator::new(order, range_size)
+                    .filter(|x| x.iter().sum::<usize>() % num_threads == t)
+                    .collect()
+            } else {
+                WindowedTupleIterator::new(order, range_size, window)
+                    .filter(|x| x.iter().sum::<usize>() % num_threads == t)
+                    .collect()
+            };
+
+            for tuple in time_tuples {
+                let mut ho_trace =  vec![0;num_traces];
+                
+                for i in 0..num_traces {
+                    for time_point in &tuple {
+                        ho_trace[i] ^= traces[i][samples[*time_point]];
+                    }
+                }
+
+                let statistic = welch_t_statistic(&ho_trace, &labels);
+                max_statistic = max_statistic.max(statistic.abs());
+
+                if statistic.abs() > threshold {
+                    let mut indices: Vec<usize> = tuple.iter().map(|&x| samples[x]).collect();
+                    indices.sort();
+                    leaking.push((indices, statistic));
+                }
+            }
+
+            result_tx.send((leaking, max_statistic)).expect("Thread could not send result");
+        });
+    }
+
+    let mut leaking = vec![];
+    let mut max_statistic: f64 = 0.0;
+
+    for _ in 0..num_threads {
+        let thread_result = result_rx.recv().expect("Main could not receive result");
+
+        leaking.extend(thread_result.0);
+        max_statistic = max_statistic.max(thread_result.1);
+    }
+
+    leaking.sort_by(|x: &TupleStatistic, y: &TupleStatistic| {
+        (y.1).abs().partial_cmp(&(x.1).abs()).expect("Could not sort")
+    });
+
+    (leaking, max_statistic)
+}
+
+/// Writes tuples and their t-statistic to file, one tuple per line
+pub fn write_statistics(path: &str, statistics: &Vec<TupleStatistic>) {
+    let file = File::create(path).expect("Could not create file.");
+    let mut writer = BufWriter::new(file);
+
+    for (tuple, statistic) in statistics {
+        let indices: Vec<String> = tuple.iter().map(|x| x.to_string()).collect();
+        writeln!(writer, "{} {:.4}", indices.join(" "), statistic).expect("Could not write statistic.");
+    }
+}
@@ -0,0 +1,47 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use options::{ByteOrder, TraceFormat};
+use readers::Trace;
+
+/// Writes bit-serialized traces to file in the given format.
+pub fn write_traces(trace_path: &str, traces: &Vec<Trace>, format: TraceFormat) {
+    let file = File::create(trace_path).expect("Could not create file.");
+    let mut writer = BufWriter::new(file);
+
+    for trace in traces {
+        match format {
+            TraceFormat::Daredevil => {
+                writer.write_all(trace).expect("Could not write trace.");
+            },
+            TraceFormat::Packed => {
+                let mut packed = vec![0u8;trace.len().div_ceil(8)];
+
+                for (i, sample) in trace.iter().enumerate() {
+                    packed[i/8] ^= (sample & 0x1) << (i%8);
+                }
+
+                writer.write_all(&packed).expect("Could not write trace.");
+            },
+            TraceFormat::Text => {
+                let line: Vec<String> = trace.iter().map(|x| x.to_string()).collect();
+                writeln!(writer, "{}", line.join(" ")).expect("Could not write trace.");
+            },
+        }
+    }
+}
+
+/// Writes inputs associated with a DCA trace to file. This is the inverse of `read_inputs`.
+pub fn write_inputs(input_path: &str, inputs: &Vec<Vec<u8>>, byte_order: ByteOrder) {
+    let file = File::create(input_path).expect("Could not create file.");
+    let mut writer = BufWriter::new(file);
+
+    for input in inputs {
+        let mut bytes = vec![0;input.len()];
+
+        for (i, &x) in input.iter().enumerate() {
+            bytes[byte_order.file_index(i, input.len())] = x;
+        }
+
+        writer.write_all(&bytes).expect("Could not write input.");
+    }
+}